@@ -2,4 +2,8 @@ export const PYTH_PUBLIC_ADDRESS = "7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE
 
 export const DEVNET_RPC_ENDPOINT = "https://api.devnet.solana.com"
 
-export const SOL_USD_PRICE_FEED_ID_HEX = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
\ No newline at end of file
+export const SOL_USD_PRICE_FEED_ID_HEX = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
+
+export const USDC_USD_PRICE_FEED_ID_HEX = "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+
+export const PYTH_RECEIVER_PROGRAM_ID = "rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ";
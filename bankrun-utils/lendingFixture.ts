@@ -0,0 +1,248 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, web3 } from "@coral-xyz/anchor";
+import { BankrunProvider } from "anchor-bankrun";
+import { TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import { ProgramTestContext, startAnchor } from "solana-bankrun";
+import { LendityFi } from "../target/types/lendity_fi";
+import LendityFiIdl from "../target/idl/lendity_fi.json";
+import { tokenBalance } from "./tokens";
+
+export type LendingFixture = {
+  context: ProgramTestContext;
+  provider: BankrunProvider;
+  program: anchor.Program<LendityFi>;
+  payer: web3.Keypair;
+  market: web3.PublicKey;
+};
+
+export type PriceMode = { spot: {} } | { ema: {} } | { conservative: {} };
+
+export const riskParams = {
+  maxLtv: new BN(7_500),
+  liquidationThreshold: new BN(8_000),
+  liquidationBonus: new BN(500),
+  liquidationCloseFactor: new BN(5_000),
+};
+
+export const interestRateModel = {
+  baseRate: new BN(0),
+  slope1: new BN(400),
+  optimalUtilization: new BN(8_000),
+  slope2: new BN(7_500),
+};
+
+// Spot priced Pyth feed without a secondary source.
+export const oracleConfig = (feedIdHex: string) => ({
+  oracleType: { pyth: {} },
+  feedId: Array.from(Buffer.from(feedIdHex.slice(2), "hex")),
+  maxAge: new BN(60),
+  maxConfidenceBps: new BN(200),
+  priceMode: { spot: {} } as PriceMode,
+  secondaryOracle: web3.PublicKey.default,
+  maxDeviationBps: new BN(0),
+});
+
+export const bankConfig = (oracle: ReturnType<typeof oracleConfig>) => ({
+  riskParams,
+  interestRateModel,
+  reserveFactor: new BN(1_000),
+  depositCap: new BN(0),
+  borrowCap: new BN(0),
+  flashLoanFeeBps: new BN(9),
+  oracle,
+});
+
+// Starts a bankrun context with the program loaded and a fresh market whose
+// admin is the provider wallet.
+export async function startLendingFixture(): Promise<LendingFixture> {
+  const context = await startAnchor("", [], []);
+  const provider = new BankrunProvider(context);
+  const program = new anchor.Program<LendityFi>(LendityFiIdl as LendityFi, provider);
+  const payer = provider.wallet.payer;
+  const market = web3.Keypair.generate();
+
+  await program.methods
+    .initializeMarket()
+    .accounts({ signer: payer.publicKey, market: market.publicKey })
+    .signers([market])
+    .rpc();
+
+  return { context, provider, program, payer, market: market.publicKey };
+}
+
+export function bankAddress(fixture: LendingFixture, mint: web3.PublicKey) {
+  return web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("bank"), fixture.market.toBuffer(), mint.toBuffer()],
+    fixture.program.programId
+  )[0];
+}
+
+export function bankTokenAccountAddress(fixture: LendingFixture, mint: web3.PublicKey) {
+  return web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("bank_token_account"), fixture.market.toBuffer(), mint.toBuffer()],
+    fixture.program.programId
+  )[0];
+}
+
+export function receiptMintAddress(fixture: LendingFixture, mint: web3.PublicKey) {
+  return web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("receipt_mint"), fixture.market.toBuffer(), mint.toBuffer()],
+    fixture.program.programId
+  )[0];
+}
+
+export function userAddress(fixture: LendingFixture, owner: web3.PublicKey) {
+  return web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("user"), fixture.market.toBuffer(), owner.toBuffer()],
+    fixture.program.programId
+  )[0];
+}
+
+export async function currentTimestamp(fixture: LendingFixture): Promise<BN> {
+  const clock = await fixture.context.banksClient.getClock();
+  return new BN(clock.unixTimestamp.toString());
+}
+
+// Gives `owner` lamports to pay for its own accounts.
+export function fundWallet(fixture: LendingFixture, owner: web3.PublicKey) {
+  fixture.context.setAccount(owner, {
+    lamports: 10 * web3.LAMPORTS_PER_SOL,
+    data: Buffer.alloc(0),
+    owner: web3.SystemProgram.programId,
+    executable: false,
+  });
+}
+
+export async function initializeBank(
+  fixture: LendingFixture,
+  mint: web3.PublicKey,
+  config: ReturnType<typeof bankConfig>,
+  tokenProgram: web3.PublicKey = TOKEN_PROGRAM_ID
+) {
+  await fixture.program.methods
+    .initializeBank(config)
+    .accountsPartial({
+      signer: fixture.payer.publicKey,
+      market: fixture.market,
+      mint,
+      tokenProgram,
+    })
+    .rpc();
+}
+
+export async function initializeUser(fixture: LendingFixture, owner: web3.Keypair) {
+  await fixture.program.methods
+    .initializeUser()
+    .accountsPartial({ signer: owner.publicKey, market: fixture.market })
+    .signers([owner])
+    .rpc();
+}
+
+// Deposits `amount` from the owner's associated token account and returns the
+// receipt tokens minted for it.
+export async function deposit(
+  fixture: LendingFixture,
+  owner: web3.Keypair,
+  mint: web3.PublicKey,
+  amount: BN,
+  tokenProgram: web3.PublicKey = TOKEN_PROGRAM_ID
+): Promise<BN> {
+  const receiptTokenAccount = getAssociatedTokenAddressSync(
+    receiptMintAddress(fixture, mint),
+    owner.publicKey,
+    false,
+    tokenProgram
+  );
+  const receiptsBefore = await tokenBalanceOrZero(fixture, receiptTokenAccount);
+
+  await fixture.program.methods
+    .deposit(amount)
+    .accountsPartial({
+      signer: owner.publicKey,
+      market: fixture.market,
+      mint,
+      tokenProgram,
+    })
+    .signers([owner])
+    .rpc();
+
+  const receiptsAfter = await tokenBalance(fixture.context, receiptTokenAccount);
+  return receiptsAfter.sub(receiptsBefore);
+}
+
+// Deposits `amount` and locks every receipt token minted for it as
+// collateral.
+export async function depositCollateral(
+  fixture: LendingFixture,
+  owner: web3.Keypair,
+  mint: web3.PublicKey,
+  amount: BN,
+  tokenProgram: web3.PublicKey = TOKEN_PROGRAM_ID
+): Promise<BN> {
+  const receipts = await deposit(fixture, owner, mint, amount, tokenProgram);
+
+  await fixture.program.methods
+    .depositCollateral(receipts)
+    .accountsPartial({
+      signer: owner.publicKey,
+      market: fixture.market,
+      mint,
+      userReceiptTokenAccount: getAssociatedTokenAddressSync(
+        receiptMintAddress(fixture, mint),
+        owner.publicKey,
+        false,
+        tokenProgram
+      ),
+      tokenProgram,
+    })
+    .signers([owner])
+    .rpc();
+
+  return receipts;
+}
+
+// Remaining accounts entry for one position, in the layout `compute_health`
+// expects.
+export function healthAccounts(
+  bank: web3.PublicKey,
+  priceUpdate: web3.PublicKey,
+  secondaryPriceFeed?: web3.PublicKey
+): web3.AccountMeta[] {
+  return [bank, priceUpdate, secondaryPriceFeed]
+    .filter((pubkey): pubkey is web3.PublicKey => pubkey !== undefined)
+    .map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }));
+}
+
+// Asserts that `promise` fails with the program error called `name`.
+export async function expectProgramError(
+  fixture: LendingFixture,
+  promise: Promise<unknown>,
+  name: string
+) {
+  const idlError = fixture.program.idl.errors.find(
+    (error) => error.name.toLowerCase() === name.toLowerCase()
+  );
+  if (!idlError) {
+    throw new Error(`Unknown program error ${name}`);
+  }
+
+  let thrown: any;
+  try {
+    await promise;
+  } catch (err) {
+    thrown = err;
+  }
+  expect(thrown).toBeDefined();
+
+  const code = thrown.error?.errorCode?.number ?? thrown.code;
+  if (typeof code === "number") {
+    expect(code).toBe(idlError.code);
+  } else {
+    expect(String(thrown)).toContain(`custom program error: 0x${idlError.code.toString(16)}`);
+  }
+}
+
+async function tokenBalanceOrZero(fixture: LendingFixture, address: web3.PublicKey) {
+  const account = await fixture.context.banksClient.getAccount(address);
+  return account ? tokenBalance(fixture.context, address) : new BN(0);
+}
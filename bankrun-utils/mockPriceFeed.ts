@@ -0,0 +1,93 @@
+import { createHash } from "crypto";
+import { BN, web3 } from "@coral-xyz/anchor";
+import { ProgramTestContext } from "solana-bankrun";
+import { PYTH_RECEIVER_PROGRAM_ID } from "./constants";
+
+export type MockPrice = {
+  price: BN;
+  conf: BN;
+  exponent: number;
+  publishTime: BN;
+};
+
+// Layout of the program's `PriceFeed` account: discriminator, i64 price,
+// u64 conf, i32 exponent, i64 publish time.
+export function encodePriceFeed(mockPrice: MockPrice): Buffer {
+  const discriminator = createHash("sha256")
+    .update("account:PriceFeed")
+    .digest()
+    .subarray(0, 8);
+
+  const exponent = Buffer.alloc(4);
+  exponent.writeInt32LE(mockPrice.exponent);
+
+  return Buffer.concat([
+    discriminator,
+    mockPrice.price.toTwos(64).toArrayLike(Buffer, "le", 8),
+    mockPrice.conf.toArrayLike(Buffer, "le", 8),
+    exponent,
+    mockPrice.publishTime.toTwos(64).toArrayLike(Buffer, "le", 8),
+  ]);
+}
+
+// Writes a secondary oracle account that a bank can be configured to read.
+export function setMockPriceFeed(
+  context: ProgramTestContext,
+  address: web3.PublicKey,
+  owner: web3.PublicKey,
+  mockPrice: MockPrice
+) {
+  context.setAccount(address, {
+    lamports: web3.LAMPORTS_PER_SOL,
+    data: encodePriceFeed(mockPrice),
+    owner,
+    executable: false,
+  });
+}
+
+// Layout of a fully verified Pyth `PriceUpdateV2` account: discriminator,
+// write authority, verification level, price message and posted slot. The
+// EMA price and confidence are set to the spot values.
+export function encodePriceUpdate(feedIdHex: string, mockPrice: MockPrice): Buffer {
+  const discriminator = createHash("sha256")
+    .update("account:PriceUpdateV2")
+    .digest()
+    .subarray(0, 8);
+
+  const exponent = Buffer.alloc(4);
+  exponent.writeInt32LE(mockPrice.exponent);
+
+  const price = mockPrice.price.toTwos(64).toArrayLike(Buffer, "le", 8);
+  const conf = mockPrice.conf.toArrayLike(Buffer, "le", 8);
+  const publishTime = mockPrice.publishTime.toTwos(64).toArrayLike(Buffer, "le", 8);
+
+  return Buffer.concat([
+    discriminator,
+    Buffer.alloc(32), // write authority
+    Buffer.from([1]), // VerificationLevel::Full
+    Buffer.from(feedIdHex.slice(2), "hex"),
+    price,
+    conf,
+    exponent,
+    publishTime,
+    publishTime, // previous publish time
+    price, // EMA price
+    conf, // EMA confidence
+    Buffer.alloc(8), // posted slot
+  ]);
+}
+
+// Writes a Pyth price update account that a bank's primary oracle can read.
+export function setMockPriceUpdate(
+  context: ProgramTestContext,
+  address: web3.PublicKey,
+  feedIdHex: string,
+  mockPrice: MockPrice
+) {
+  context.setAccount(address, {
+    lamports: web3.LAMPORTS_PER_SOL,
+    data: encodePriceUpdate(feedIdHex, mockPrice),
+    owner: new web3.PublicKey(PYTH_RECEIVER_PROGRAM_ID),
+    executable: false,
+  });
+}
@@ -0,0 +1,147 @@
+import { BN, web3 } from "@coral-xyz/anchor";
+import { BankrunProvider } from "anchor-bankrun";
+import {
+  ExtensionType,
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  createInitializeMint2Instruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMintToInstruction,
+  createTransferInstruction,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+  unpackAccount,
+} from "@solana/spl-token";
+import { ProgramTestContext } from "solana-bankrun";
+
+export type TransferFee = {
+  basisPoints: number;
+  maximumFee: bigint;
+};
+
+// Creates a mint owned by the provider wallet. Passing a transfer fee creates
+// a Token-2022 mint with the `TransferFeeConfig` extension.
+export async function createTestMint(
+  provider: BankrunProvider,
+  decimals: number,
+  tokenProgram: web3.PublicKey = TOKEN_PROGRAM_ID,
+  transferFee?: TransferFee
+): Promise<web3.PublicKey> {
+  const payer = provider.wallet.payer;
+  const mint = web3.Keypair.generate();
+  const extensions = transferFee ? [ExtensionType.TransferFeeConfig] : [];
+  const space = getMintLen(extensions);
+  const rent = await provider.context.banksClient.getRent();
+
+  const tx = new web3.Transaction().add(
+    web3.SystemProgram.createAccount({
+      fromPubkey: payer.publicKey,
+      newAccountPubkey: mint.publicKey,
+      space,
+      lamports: Number(rent.minimumBalance(BigInt(space))),
+      programId: tokenProgram,
+    })
+  );
+  if (transferFee) {
+    tx.add(
+      createInitializeTransferFeeConfigInstruction(
+        mint.publicKey,
+        payer.publicKey,
+        payer.publicKey,
+        transferFee.basisPoints,
+        transferFee.maximumFee,
+        tokenProgram
+      )
+    );
+  }
+  tx.add(
+    createInitializeMint2Instruction(
+      mint.publicKey,
+      decimals,
+      payer.publicKey,
+      null,
+      tokenProgram
+    )
+  );
+
+  await provider.sendAndConfirm(tx, [mint]);
+  return mint.publicKey;
+}
+
+// Creates `owner`'s associated token account for `mint` if it is missing.
+export async function createTokenAccount(
+  provider: BankrunProvider,
+  mint: web3.PublicKey,
+  owner: web3.PublicKey,
+  tokenProgram: web3.PublicKey = TOKEN_PROGRAM_ID
+): Promise<web3.PublicKey> {
+  const address = getAssociatedTokenAddressSync(mint, owner, true, tokenProgram);
+  const tx = new web3.Transaction().add(
+    createAssociatedTokenAccountIdempotentInstruction(
+      provider.wallet.publicKey,
+      address,
+      owner,
+      mint,
+      tokenProgram
+    )
+  );
+
+  await provider.sendAndConfirm(tx);
+  return address;
+}
+
+export async function mintTokens(
+  provider: BankrunProvider,
+  mint: web3.PublicKey,
+  destination: web3.PublicKey,
+  amount: BN,
+  tokenProgram: web3.PublicKey = TOKEN_PROGRAM_ID
+) {
+  const tx = new web3.Transaction().add(
+    createMintToInstruction(
+      mint,
+      destination,
+      provider.wallet.publicKey,
+      BigInt(amount.toString()),
+      [],
+      tokenProgram
+    )
+  );
+
+  await provider.sendAndConfirm(tx);
+}
+
+// Moves tokens out of an account owned by the provider wallet.
+export async function transferTokens(
+  provider: BankrunProvider,
+  source: web3.PublicKey,
+  destination: web3.PublicKey,
+  amount: BN,
+  tokenProgram: web3.PublicKey = TOKEN_PROGRAM_ID
+) {
+  const tx = new web3.Transaction().add(
+    createTransferInstruction(
+      source,
+      destination,
+      provider.wallet.publicKey,
+      BigInt(amount.toString()),
+      [],
+      tokenProgram
+    )
+  );
+
+  await provider.sendAndConfirm(tx);
+}
+
+export async function tokenBalance(
+  context: ProgramTestContext,
+  address: web3.PublicKey
+): Promise<BN> {
+  const account = await context.banksClient.getAccount(address);
+  const tokenAccount = unpackAccount(
+    address,
+    { ...account, data: Buffer.from(account.data) },
+    account.owner
+  );
+  return new BN(tokenAccount.amount.toString());
+}
@@ -5,9 +5,15 @@ pub const SEED: &str = "anchor";
 
 pub const ANCHOR_DISCRIMINATOR: usize = 8;
 
-pub const SOL_USD_FEED_ID_HEX: &str =
-    "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
-pub const USDC_USD_FEED_ID_HEX: &str =
-    "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+pub const MAX_POSITIONS: usize = 8;
 
-pub const MAX_AGE_PYTH: u64 = 100;
+/// Highest annual borrow rate an interest rate model may reach, in basis
+/// points (1000%).
+pub const MAX_BORROW_RATE_BPS: u64 = 100_000;
+
+/// A bank's virtual deposit shares are worth this fraction of one whole token,
+/// see `Bank::virtual_deposit_shares`.
+pub const VIRTUAL_DEPOSIT_SHARES_DIVISOR: u64 = 1_000;
+
+/// Lower bound on a bank's virtual deposit shares, for low-decimal mints.
+pub const MIN_VIRTUAL_DEPOSIT_SHARES: u64 = 1;
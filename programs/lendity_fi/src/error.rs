@@ -13,4 +13,103 @@ pub enum ErrorCode {
 
     #[msg("The account does not fall below the health factor, so can't be liquidated")]
     DoesNotFallBelowHealthFactor,
+
+    #[msg("Math operation overflowed")]
+    MathOverflow,
+
+    #[msg("The collateral bank does not match the user's collateral asset")]
+    InvalidCollateralBank,
+
+    #[msg("Invalid interest rate model")]
+    InvalidInterestRateModel,
+
+    #[msg("Reserve factor must not exceed 10000 basis points")]
+    InvalidReserveFactor,
+
+    #[msg("Signer is not the bank authority")]
+    Unauthorized,
+
+    #[msg("Not enough accumulated protocol fees")]
+    InsufficientProtocolFees,
+
+    #[msg("The user account has no free position slots")]
+    TooManyPositions,
+
+    #[msg("Invalid oracle configuration")]
+    InvalidOracleConfig,
+
+    #[msg("The price account does not match the bank's oracle config")]
+    InvalidOracleAccount,
+
+    #[msg("Oracle price is not positive")]
+    InvalidPrice,
+
+    #[msg("Oracle confidence interval is too wide")]
+    PriceTooUncertain,
+
+    #[msg("No oracle source has a fresh price")]
+    StalePrice,
+
+    #[msg("Primary and secondary oracle prices deviate too much")]
+    PriceDeviationTooHigh,
+
+    #[msg("Remaining accounts do not match the user's positions")]
+    InvalidHealthAccounts,
+
+    #[msg("Remaining collateral would not cover outstanding debt")]
+    WithdrawalLeavesUnhealthyAccount,
+
+    #[msg("Seized collateral is below the liquidator's minimum")]
+    LiquidationSlippageExceeded,
+
+    #[msg("Max LTV must be positive and below the liquidation threshold")]
+    InvalidMaxLtv,
+
+    #[msg("Liquidation threshold must not exceed 10000 basis points")]
+    InvalidLiquidationThreshold,
+
+    #[msg("Liquidation bonus would seize more than the collateral backing the debt")]
+    InvalidLiquidationBonus,
+
+    #[msg("Close factor must be between 1 and 10000 basis points")]
+    InvalidCloseFactor,
+
+    #[msg("Deposit would exceed the bank's deposit cap")]
+    DepositCapExceeded,
+
+    #[msg("Borrow would exceed the bank's borrow cap")]
+    BorrowCapExceeded,
+
+    #[msg("Signer is not the pending bank authority")]
+    NotPendingAuthority,
+
+    #[msg("Signer is not the lending market admin")]
+    NotMarketAdmin,
+
+    #[msg("Flash loan fee must not exceed 10000 basis points")]
+    InvalidFlashLoanFee,
+
+    #[msg("A flash loan is already open on this bank")]
+    FlashLoanActive,
+
+    #[msg("No flash loan is open on this bank")]
+    NoActiveFlashLoan,
+
+    #[msg("Flash borrow must be followed by a flash repay for the same bank")]
+    MissingFlashRepay,
+
+    #[msg("Flash borrow cannot be called through CPI")]
+    FlashLoanCpiNotAllowed,
+
+    #[msg("Amount is too small to mint or burn any shares")]
+    ZeroShares,
+
+    #[msg("Bank books exceed the vault token balance")]
+    VaultInsolvent,
+
+    #[msg("Mint has an extension that is unsafe for lending")]
+    UnsupportedMintExtension,
+
+    #[msg("Flash loan was not repaid in full")]
+    FlashLoanNotRepaid,
 }
@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    math::{Rounding, Wad},
+    oracle::get_oracle_price,
+    Bank, User,
+};
+
+/// USD values of a user's positions, weighted by each bank's risk parameters.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AccountHealth {
+    /// Collateral weighted by each bank's `max_ltv`.
+    pub borrow_limit: Wad,
+    /// Collateral weighted by each bank's `liquidation_threshold`.
+    pub liquidation_limit: Wad,
+    /// Outstanding debt across all banks.
+    pub debt: Wad,
+}
+
+/// Values every position of `user`. `remaining_accounts` must hold, for each
+/// position in order, the bank account, its price update account and, when
+/// the bank has one configured, its secondary price account.
+///
+/// Banks already loaded by the instruction are passed in `loaded_banks` so
+/// their in-memory state is used instead of the stale account data.
+pub fn compute_health(
+    user: &User,
+    remaining_accounts: &[AccountInfo],
+    loaded_banks: &[(Pubkey, &Bank)],
+    clock: &Clock,
+) -> Result<AccountHealth> {
+    let mut health = AccountHealth::default();
+    let mut accounts = remaining_accounts.iter();
+
+    for position in user.positions.iter() {
+        let bank_info = accounts.next().ok_or(ErrorCode::InvalidHealthAccounts)?;
+        require_keys_eq!(
+            bank_info.key(),
+            position.bank,
+            ErrorCode::InvalidHealthAccounts
+        );
+
+        let bank = match loaded_banks.iter().find(|(key, _)| *key == position.bank) {
+            Some((_, bank)) => (*bank).clone(),
+            None => {
+                let mut bank = load_bank(bank_info)?;
+                require_keys_eq!(bank.market, user.market, ErrorCode::InvalidHealthAccounts);
+                bank.accrue_interest(clock.unix_timestamp)?;
+                bank
+            }
+        };
+
+        let price_info = accounts.next().ok_or(ErrorCode::InvalidHealthAccounts)?;
+        let secondary_price_info = if bank.oracle.has_secondary_oracle() {
+            Some(accounts.next().ok_or(ErrorCode::InvalidHealthAccounts)?)
+        } else {
+            None
+        };
+        let price = get_oracle_price(&bank, price_info, secondary_price_info, clock)?;
+
+        if position.deposit_shares > 0 {
+            let amount = bank.deposit_shares_to_amount(position.deposit_shares, Rounding::Down)?;
+            let value =
+                price
+                    .lower_bound()?
+                    .usd_value(amount, bank.mint_decimals, Rounding::Down)?;
+
+            health.borrow_limit = health
+                .borrow_limit
+                .try_add(value.try_mul(Wad::from_bps(bank.max_ltv), Rounding::Down)?)?;
+            health.liquidation_limit = health.liquidation_limit.try_add(
+                value.try_mul(Wad::from_bps(bank.liquidation_threshold), Rounding::Down)?,
+            )?;
+        }
+
+        if position.borrow_shares > 0 {
+            let amount = bank.borrow_shares_to_amount(position.borrow_shares, Rounding::Up)?;
+            let value = price
+                .upper_bound()?
+                .usd_value(amount, bank.mint_decimals, Rounding::Up)?;
+
+            health.debt = health.debt.try_add(value)?;
+        }
+    }
+
+    Ok(health)
+}
+
+fn load_bank(bank_info: &AccountInfo) -> Result<Bank> {
+    require_keys_eq!(
+        *bank_info.owner,
+        crate::ID,
+        ErrorCode::InvalidHealthAccounts
+    );
+    Bank::try_deserialize(&mut &bank_info.try_borrow_data()?[..])
+}
@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, Bank};
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = bank.pending_authority == signer.key() @ ErrorCode::NotPendingAuthority,
+    )]
+    pub bank: Account<'info, Bank>,
+}
+
+pub fn accept_authority_handler(ctx: Context<AcceptAuthority>) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+    bank.authority = bank.pending_authority;
+    bank.pending_authority = Pubkey::default();
+    Ok(())
+}
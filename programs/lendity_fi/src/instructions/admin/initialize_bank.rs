@@ -1,20 +1,31 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        self,
+        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    },
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
 
-use crate::{Bank, ANCHOR_DISCRIMINATOR};
+use crate::{error::ErrorCode, math::WAD, Bank, BankConfig, LendingMarket, ANCHOR_DISCRIMINATOR};
 
 #[derive(Accounts)]
 pub struct InitializeBank<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    #[account(
+        constraint = market.admin == signer.key() @ ErrorCode::NotMarketAdmin,
+    )]
+    pub market: Account<'info, LendingMarket>,
+
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
     init,
     payer = signer,
     space = ANCHOR_DISCRIMINATOR + Bank::INIT_SPACE,
-    seeds = [mint.key().as_ref()],
+    seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
     bump,
   )]
     pub bank: Account<'info, Bank>,
@@ -25,25 +36,75 @@ pub struct InitializeBank<'info> {
     token::authority = bank_token_account,
     token::token_program = token_program,
     payer = signer,
-    seeds = [b"bank_token_account", mint.key().as_ref()],
+    seeds = [b"bank_token_account", market.key().as_ref(), mint.key().as_ref()],
     bump
   )]
     pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    #[account(
+    init,
+    mint::decimals = mint.decimals,
+    mint::authority = receipt_mint,
+    mint::token_program = token_program,
+    payer = signer,
+    seeds = [b"receipt_mint", market.key().as_ref(), mint.key().as_ref()],
+    bump
+  )]
+    pub receipt_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+    init,
+    token::mint = receipt_mint,
+    token::authority = collateral_vault,
+    token::token_program = token_program,
+    payer = signer,
+    seeds = [b"collateral_vault", market.key().as_ref(), mint.key().as_ref()],
+    bump
+  )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
     pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
 }
 
-pub fn initialize_bank_handler(
-    ctx: Context<InitializeBank>,
-    liquidation_threshold: u64,
-    max_ltv: u64,
-) -> Result<()> {
+pub fn initialize_bank_handler(ctx: Context<InitializeBank>, config: BankConfig) -> Result<()> {
+    config.validate()?;
+    validate_mint_extensions(&ctx.accounts.mint.to_account_info())?;
+
     let bank = &mut ctx.accounts.bank;
+    bank.market = ctx.accounts.market.key();
     bank.authority = ctx.accounts.signer.key();
     bank.mint_address = ctx.accounts.mint.key();
-    bank.max_ltv = max_ltv;
-    bank.liquidation_threshold = liquidation_threshold;
-    bank.interest_rate = 0.05;
+    bank.mint_decimals = ctx.accounts.mint.decimals;
+    bank.virtual_deposit_shares = Bank::virtual_deposit_shares_for(ctx.accounts.mint.decimals)?;
+    bank.set_config(config);
+    bank.supply_index = WAD;
+    bank.borrow_index = WAD;
+    bank.last_updated = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+/// Rejects Token-2022 mints whose extensions let the issuer move, freeze or
+/// lock tokens held by the bank.
+fn validate_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+
+    for extension in mint_state.get_extension_types()? {
+        require!(
+            !matches!(
+                extension,
+                ExtensionType::PermanentDelegate
+                    | ExtensionType::DefaultAccountState
+                    | ExtensionType::NonTransferable
+            ),
+            ErrorCode::UnsupportedMintExtension
+        );
+    }
+
     Ok(())
 }
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::{LendingMarket, ANCHOR_DISCRIMINATOR};
+
+#[derive(Accounts)]
+pub struct InitializeMarket<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = signer,
+        space = ANCHOR_DISCRIMINATOR + LendingMarket::INIT_SPACE,
+    )]
+    pub market: Account<'info, LendingMarket>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_market_handler(ctx: Context<InitializeMarket>) -> Result<()> {
+    ctx.accounts.market.admin = ctx.accounts.signer.key();
+    Ok(())
+}
@@ -1,17 +1,19 @@
 use anchor_lang::prelude::*;
 
-use crate::{User, ANCHOR_DISCRIMINATOR};
+use crate::{LendingMarket, User, ANCHOR_DISCRIMINATOR};
 
 #[derive(Accounts)]
 pub struct InitializeUser<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    pub market: Account<'info, LendingMarket>,
+
     #[account(
     init,
     payer = signer,
     space = ANCHOR_DISCRIMINATOR + User::INIT_SPACE,
-    seeds = [signer.key().as_ref()],
+    seeds = [b"user", market.key().as_ref(), signer.key().as_ref()],
     bump,
   )]
     pub user_account: Account<'info, User>,
@@ -19,9 +21,9 @@ pub struct InitializeUser<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn initialize_user_handler(ctx: Context<InitializeUser>, usdc_address: Pubkey) -> Result<()> {
+pub fn initialize_user_handler(ctx: Context<InitializeUser>) -> Result<()> {
     let user_account = &mut ctx.accounts.user_account;
     user_account.owner = ctx.accounts.signer.key();
-    user_account.usdc_address = usdc_address;
+    user_account.market = ctx.accounts.market.key();
     Ok(())
 }
@@ -1,5 +1,20 @@
+pub mod initialize_market;
+pub use initialize_market::*;
+
 pub mod initialize_user;
 pub use initialize_user::*;
 
 pub mod initialize_bank;
 pub use initialize_bank::*;
+
+pub mod withdraw_protocol_fees;
+pub use withdraw_protocol_fees::*;
+
+pub mod update_bank_config;
+pub use update_bank_config::*;
+
+pub mod propose_authority;
+pub use propose_authority::*;
+
+pub mod accept_authority;
+pub use accept_authority::*;
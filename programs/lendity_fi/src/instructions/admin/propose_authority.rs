@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, Bank};
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = bank.authority == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub bank: Account<'info, Bank>,
+}
+
+/// Proposing the default key cancels a pending transfer.
+pub fn propose_authority_handler(
+    ctx: Context<ProposeAuthority>,
+    new_authority: Pubkey,
+) -> Result<()> {
+    ctx.accounts.bank.pending_authority = new_authority;
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, Bank, BankConfig};
+
+#[derive(Accounts)]
+pub struct UpdateBankConfig<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = bank.authority == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub bank: Account<'info, Bank>,
+}
+
+#[event]
+pub struct BankConfigUpdated {
+    pub bank: Pubkey,
+    pub old_config: BankConfig,
+    pub new_config: BankConfig,
+}
+
+pub fn update_bank_config_handler(
+    ctx: Context<UpdateBankConfig>,
+    config: BankConfig,
+) -> Result<()> {
+    config.validate()?;
+
+    let bank = &mut ctx.accounts.bank;
+
+    // settle interest at the old rate model before switching :-
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
+
+    let old_config = bank.config();
+    bank.set_config(config);
+
+    emit!(BankConfigUpdated {
+        bank: bank.key(),
+        old_config,
+        new_config: config,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{error::ErrorCode, Bank, LendingMarket};
+
+#[derive(Accounts)]
+pub struct WithdrawProtocolFees<'info> {
+    pub signer: Signer<'info>,
+
+    pub market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
+        bump,
+        constraint = bank.authority == signer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub bank: Account<'info, Bank>,
+
+    #[account(
+        mut,
+        seeds = [b"bank_token_account", market.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn withdraw_protocol_fees_handler(
+    ctx: Context<WithdrawProtocolFees>,
+    amount: u64,
+) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
+
+    if amount > bank.accumulated_protocol_fees {
+        return Err(ErrorCode::InsufficientProtocolFees.into());
+    }
+
+    let fee_transfer_accounts = TransferChecked {
+        from: ctx.accounts.bank_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.treasury_token_account.to_account_info(),
+        authority: ctx.accounts.bank_token_account.to_account_info(),
+    };
+
+    let market_key = ctx.accounts.market.key();
+    let mint_key = ctx.accounts.mint.key();
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"bank_token_account",
+        market_key.as_ref(),
+        mint_key.as_ref(),
+        &[ctx.bumps.bank_token_account],
+    ]];
+
+    let fee_cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        fee_transfer_accounts,
+    )
+    .with_signer(signer_seeds);
+
+    transfer_checked(fee_cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    bank.accumulated_protocol_fees -= amount;
+
+    bank.check_vault_solvency(&mut ctx.accounts.bank_token_account)?;
+
+    Ok(())
+}
@@ -1,14 +1,12 @@
-use std::f64::consts::E;
-
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
 use crate::{
-    error::ErrorCode, Bank, User, MAX_AGE_PYTH, SOL_USD_FEED_ID_HEX, USDC_USD_FEED_ID_HEX,
+    error::ErrorCode, health::compute_health, math::Rounding, oracle::get_oracle_price, Bank,
+    LendingMarket, User,
 };
 
 #[derive(Accounts)]
@@ -16,18 +14,20 @@ pub struct Borrow<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    pub market: Account<'info, LendingMarket>,
+
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        seeds = [mint.key().as_ref()],
+        seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank: Account<'info, Bank>,
 
     #[account(
         mut,
-        seeds = [signer.key().as_ref()],
+        seeds = [b"user", market.key().as_ref(), signer.key().as_ref()],
         bump,
     )]
     pub user_account: Account<'info, User>,
@@ -43,63 +43,49 @@ pub struct Borrow<'info> {
 
     #[account(
         mut,
-        seeds = [b"bank_token_account", mint.key().as_ref()],
+        seeds = [b"bank_token_account", market.key().as_ref(), mint.key().as_ref()],
         bump
     )]
     pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub price_update: Account<'info, PriceUpdateV2>,
+    /// CHECK: validated against the bank's oracle config
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the bank's oracle config
+    pub secondary_price_update: Option<UncheckedAccount<'info>>,
 
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
+/// Remaining accounts carry the bank and price accounts of every position the
+/// user holds, in the layout expected by `compute_health`.
 pub fn borrow_handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
     let user = &mut ctx.accounts.user_account;
     let bank = &mut ctx.accounts.bank;
-    let price_update = &mut ctx.accounts.price_update;
-    let total_collateral: u64;
 
-    // calculating the borrowable amount :-
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            let sol_usd_feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID_HEX)?;
-            let sol_usd_price = price_update.get_price_no_older_than(
-                &Clock::get()?,
-                MAX_AGE_PYTH,
-                &sol_usd_feed_id,
-            )?;
-
-            let total_deposit_with_interest_accumulated =
-                calculate_total_deposit_with_interest_accumulated(
-                    user.last_updated,
-                    user.deposited_sol,
-                    bank.interest_rate,
-                )?;
-            total_collateral = sol_usd_price.price as u64 * total_deposit_with_interest_accumulated;
-        }
-        _ => {
-            let usdc_usd_feed_id = get_feed_id_from_hex(USDC_USD_FEED_ID_HEX)?;
-            let usdc_usd_price = price_update.get_price_no_older_than(
-                &Clock::get()?,
-                MAX_AGE_PYTH,
-                &usdc_usd_feed_id,
-            )?;
-
-            let total_deposit_with_interest_accumulated =
-                calculate_total_deposit_with_interest_accumulated(
-                    user.last_updated,
-                    user.deposited_usdc,
-                    bank.interest_rate,
-                )?;
-            total_collateral =
-                usdc_usd_price.price as u64 * total_deposit_with_interest_accumulated;
-        }
-    }
+    let clock = Clock::get()?;
+    bank.accrue_interest(clock.unix_timestamp)?;
 
-    let borrowable_amount = total_collateral * bank.liquidation_threshold;
-    if borrowable_amount < amount {
+    // calculating the borrowable amount :-
+    let price = get_oracle_price(
+        bank,
+        &ctx.accounts.price_update,
+        ctx.accounts.secondary_price_update.as_deref(),
+        &clock,
+    )?;
+    let health = compute_health(
+        user,
+        ctx.remaining_accounts,
+        &[(bank.key(), &**bank)],
+        &clock,
+    )?;
+
+    let borrow_value = price
+        .upper_bound()?
+        .usd_value(amount, bank.mint_decimals, Rounding::Up)?;
+    if health.debt.try_add(borrow_value)? > health.borrow_limit {
         return Err(ErrorCode::OverBorrowableAmount.into());
     }
 
@@ -112,10 +98,12 @@ pub fn borrow_handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
         authority: ctx.accounts.bank_token_account.to_account_info(),
     };
 
+    let market_key = ctx.accounts.market.key();
     let mint_key = ctx.accounts.mint.key();
 
     let signer_seeds: &[&[&[u8]]] = &[&[
         b"bank_token_account",
+        market_key.as_ref(),
         mint_key.as_ref(),
         &[ctx.bumps.bank_token_account],
     ]];
@@ -129,43 +117,16 @@ pub fn borrow_handler(ctx: Context<Borrow>, amount: u64) -> Result<()> {
 
     // states update :-
 
-    let mut total_borrowed = bank.total_borrowed;
-    let mut total_borrowed_shares = bank.total_borrowed_shares;
+    let user_shares = bank.borrow_amount_to_shares(amount, Rounding::Up)?;
 
-    if total_borrowed == 0 {
-        total_borrowed = amount;
-        total_borrowed_shares = amount;
-    }
-
-    let borrow_ratio = amount.checked_div(total_borrowed).unwrap();
-    let user_shares = total_borrowed_shares.checked_mul(borrow_ratio).unwrap();
-
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            user.borrowed_usdc += amount;
-            user.borrowed_usdc_shares += user_shares;
-        }
-        _ => {
-            user.borrowed_sol += amount;
-            user.borrowed_sol_shares += user_shares;
-        }
-    }
+    let position = user.get_or_create_position(bank.key(), ctx.accounts.mint.key())?;
+    position.borrow_shares += user_shares;
 
     bank.total_borrowed += amount;
     bank.total_borrowed_shares += user_shares;
+    bank.check_borrow_cap()?;
 
-    user.last_updated_borrowed = Clock::get()?.unix_timestamp;
+    bank.check_vault_solvency(&mut ctx.accounts.bank_token_account)?;
 
     Ok(())
 }
-
-pub fn calculate_total_deposit_with_interest_accumulated(
-    last_updated: i64,
-    total_deposit: u64,
-    interest_rate: f64,
-) -> Result<u64> {
-    let time_diff = Clock::get()?.unix_timestamp - last_updated;
-    let total_deposit_with_interest_accumulated =
-        (total_deposit as f64 * E.powf(interest_rate * time_diff as f64)) as u64;
-    Ok(total_deposit_with_interest_accumulated)
-}
@@ -1,32 +1,27 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token_interface::{mint_to, Mint, MintTo, TokenAccount, TokenInterface},
 };
 
-use crate::{Bank, User};
+use crate::{error::ErrorCode, math::Rounding, transfer_in_measured, Bank, LendingMarket};
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    pub market: Account<'info, LendingMarket>,
+
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        seeds = [mint.key().as_ref()],
+        seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank: Account<'info, Bank>,
 
-    #[account(
-        mut,
-        seeds = [signer.key().as_ref()],
-        bump,
-    )]
-    pub user_account: Account<'info, User>,
-
     #[account(
         mut,
         associated_token::mint = mint,
@@ -37,61 +32,81 @@ pub struct Deposit<'info> {
 
     #[account(
         mut,
-        seeds = [b"bank_token_account", mint.key().as_ref()],
+        seeds = [b"bank_token_account", market.key().as_ref(), mint.key().as_ref()],
         bump
     )]
     pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    #[account(
+        mut,
+        seeds = [b"receipt_mint", market.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program
+    )]
+    pub user_receipt_token_account: InterfaceAccount<'info, TokenAccount>,
+
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
+/// Mints one receipt token per deposit share. Receipts count as collateral
+/// only once locked with `deposit_collateral`.
 pub fn deposit_handler(ctx: Context<Deposit>, amount: u64) -> Result<()> {
-    let deposit_transfer_accounts = TransferChecked {
-        from: ctx.accounts.user_token_account.to_account_info(),
-        mint: ctx.accounts.mint.to_account_info(),
-        to: ctx.accounts.bank_token_account.to_account_info(),
-        authority: ctx.accounts.signer.to_account_info(),
+    ctx.accounts
+        .bank
+        .accrue_interest(Clock::get()?.unix_timestamp)?;
+
+    let received = transfer_in_measured(
+        &ctx.accounts.token_program,
+        &ctx.accounts.user_token_account,
+        &ctx.accounts.mint,
+        &mut ctx.accounts.bank_token_account,
+        &ctx.accounts.signer,
+        amount,
+    )?;
+
+    // calculating the shares :-
+    let bank = &mut ctx.accounts.bank;
+
+    let user_shares = bank.deposit_amount_to_shares(received, Rounding::Down)?;
+    require!(user_shares > 0, ErrorCode::ZeroShares);
+
+    // minting the receipt tokens :-
+    let mint_to_accounts = MintTo {
+        mint: ctx.accounts.receipt_mint.to_account_info(),
+        to: ctx.accounts.user_receipt_token_account.to_account_info(),
+        authority: ctx.accounts.receipt_mint.to_account_info(),
     };
 
-    let deposit_cpi_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        deposit_transfer_accounts,
-    );
+    let market_key = ctx.accounts.market.key();
+    let mint_key = ctx.accounts.mint.key();
 
-    transfer_checked(deposit_cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"receipt_mint",
+        market_key.as_ref(),
+        mint_key.as_ref(),
+        &[ctx.bumps.receipt_mint],
+    ]];
 
-    // calculating the shares :-
-    let bank = &mut ctx.accounts.bank;
-    let user = &mut ctx.accounts.user_account;
-
-    let mut total_deposits = bank.total_deposits;
-    let mut total_deposits_shares = bank.total_deposits_shares;
-
-    if total_deposits == 0 {
-        total_deposits = amount;
-        total_deposits_shares = amount;
-    }
-
-    let deposit_ratio = amount.checked_div(total_deposits).unwrap();
-    let user_shares = total_deposits_shares.checked_mul(deposit_ratio).unwrap();
-
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            user.deposited_usdc += amount;
-            user.deposited_usdc_shares += user_shares;
-        }
-        _ => {
-            user.deposited_sol += amount;
-            user.deposited_sol_shares += user_shares;
-        }
-    }
-
-    bank.total_deposits += amount;
-    bank.total_deposits_shares += user_shares;
+    let mint_to_cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        mint_to_accounts,
+    )
+    .with_signer(signer_seeds);
+    mint_to(mint_to_cpi_ctx, user_shares)?;
 
-    user.last_updated = Clock::get()?.unix_timestamp;
+    bank.total_deposits += received;
+    bank.total_deposits_shares += user_shares;
+    bank.check_deposit_cap()?;
 
     Ok(())
 }
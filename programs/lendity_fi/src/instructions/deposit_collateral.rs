@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{error::ErrorCode, Bank, LendingMarket, User};
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    pub signer: Signer<'info>,
+
+    pub market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+
+    #[account(
+        mut,
+        seeds = [b"user", market.key().as_ref(), signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Account<'info, User>,
+
+    #[account(
+        seeds = [b"receipt_mint", market.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = signer,
+        token::token_program = token_program,
+    )]
+    pub user_receipt_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"collateral_vault", market.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Locks `shares` receipt tokens so they count as collateral.
+pub fn deposit_collateral_handler(ctx: Context<DepositCollateral>, shares: u64) -> Result<()> {
+    require!(shares > 0, ErrorCode::ZeroShares);
+
+    let lock_transfer_accounts = TransferChecked {
+        from: ctx.accounts.user_receipt_token_account.to_account_info(),
+        mint: ctx.accounts.receipt_mint.to_account_info(),
+        to: ctx.accounts.collateral_vault.to_account_info(),
+        authority: ctx.accounts.signer.to_account_info(),
+    };
+
+    let lock_cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        lock_transfer_accounts,
+    );
+    transfer_checked(lock_cpi_ctx, shares, ctx.accounts.receipt_mint.decimals)?;
+
+    let position = ctx
+        .accounts
+        .user_account
+        .get_or_create_position(ctx.accounts.bank.key(), ctx.accounts.mint.key())?;
+    position.deposit_shares += shares;
+
+    Ok(())
+}
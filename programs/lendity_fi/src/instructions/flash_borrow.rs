@@ -0,0 +1,123 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+        sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
+    Discriminator,
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::ErrorCode, instruction::FlashRepay as FlashRepayIx, Bank, LendingMarket};
+
+/// Position of the bank in the `FlashRepay` account list.
+const FLASH_REPAY_BANK_INDEX: usize = 3;
+
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+
+    #[account(
+        mut,
+        seeds = [b"bank_token_account", market.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: address is checked against the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// Lends `amount` without collateral. A `flash_repay` for the same bank must
+/// appear later in the same transaction, returning `amount` plus the fee.
+pub fn flash_borrow_handler(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    // The repayment lookup below only sees top level instructions.
+    require!(
+        get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT,
+        ErrorCode::FlashLoanCpiNotAllowed
+    );
+    require!(bank.flash_loan_amount == 0, ErrorCode::FlashLoanActive);
+    require!(amount > 0, ErrorCode::InsufficientFunds);
+
+    // looking for the matching repayment :-
+    let instructions = ctx.accounts.instructions.to_account_info();
+    let current_index = load_current_index_checked(&instructions)? as usize;
+    let mut index = current_index + 1;
+    let mut has_repay = false;
+
+    while let Ok(ix) = load_instruction_at_checked(index, &instructions) {
+        if ix.program_id == crate::ID
+            && ix.data.get(..8) == Some(&FlashRepayIx::DISCRIMINATOR[..])
+            && ix
+                .accounts
+                .get(FLASH_REPAY_BANK_INDEX)
+                .is_some_and(|account| account.pubkey == bank.key())
+        {
+            has_repay = true;
+            break;
+        }
+        index += 1;
+    }
+    require!(has_repay, ErrorCode::MissingFlashRepay);
+
+    // flash borrow transfer cpi :-
+
+    let borrow_transfer_accounts = TransferChecked {
+        from: ctx.accounts.bank_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.user_token_account.to_account_info(),
+        authority: ctx.accounts.bank_token_account.to_account_info(),
+    };
+
+    let market_key = ctx.accounts.market.key();
+    let mint_key = ctx.accounts.mint.key();
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"bank_token_account",
+        market_key.as_ref(),
+        mint_key.as_ref(),
+        &[ctx.bumps.bank_token_account],
+    ]];
+
+    let borrow_cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        borrow_transfer_accounts,
+    )
+    .with_signer(signer_seeds);
+    transfer_checked(borrow_cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    bank.flash_loan_amount = amount;
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    error::ErrorCode,
+    gross_up_for_transfer_fee,
+    math::{Rounding, Wad},
+    transfer_in_measured, Bank, LendingMarket,
+};
+
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    pub signer: Signer<'info>,
+
+    pub market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // must stay at the index `flash_borrow` looks up :-
+    #[account(
+        mut,
+        seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+
+    #[account(
+        mut,
+        seeds = [b"bank_token_account", market.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = signer,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Returns the open flash loan plus the fee, which is paid to depositors.
+pub fn flash_repay_handler(ctx: Context<FlashRepay>) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    let amount = bank.flash_loan_amount;
+    require!(amount > 0, ErrorCode::NoActiveFlashLoan);
+
+    let fee = Wad::from_bps(bank.flash_loan_fee_bps).apply_to(amount, Rounding::Up)?;
+    let repay_amount = amount.checked_add(fee).ok_or(ErrorCode::MathOverflow)?;
+
+    // grossed up so the full amount still arrives after any transfer fee :-
+    let transfer_amount = gross_up_for_transfer_fee(&ctx.accounts.mint, repay_amount)?;
+    let received = transfer_in_measured(
+        &ctx.accounts.token_program,
+        &ctx.accounts.user_token_account,
+        &ctx.accounts.mint,
+        &mut ctx.accounts.bank_token_account,
+        &ctx.accounts.signer,
+        transfer_amount,
+    )?;
+    require!(received >= repay_amount, ErrorCode::FlashLoanNotRepaid);
+
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
+    bank.flash_loan_amount = 0;
+
+    // With no depositors to credit, the fee is kept by the protocol.
+    if bank.total_deposits_shares > 0 {
+        bank.distribute_to_depositors(fee)?;
+    } else {
+        bank.accumulated_protocol_fees = bank
+            .accumulated_protocol_fees
+            .checked_add(fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    Ok(())
+}
@@ -1,56 +1,79 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token_interface::{
+        burn, transfer_checked, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
 };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
 
 use crate::{
-    error::ErrorCode, Bank, User, MAX_AGE_PYTH, SOL_USD_FEED_ID_HEX, USDC_USD_FEED_ID_HEX,
+    error::ErrorCode,
+    health::compute_health,
+    math::{Rounding, Wad},
+    oracle::get_oracle_price,
+    transfer_in_measured, Bank, LendingMarket, User,
 };
 
-use super::calculate_total_deposit_with_interest_accumulated;
-
 #[derive(Accounts)]
 pub struct Liquidate<'info> {
     #[account(mut)]
     pub liquidator: Signer<'info>,
 
+    pub market: Account<'info, LendingMarket>,
+
+    /// CHECK: only used to derive the borrower's user account
+    pub borrower: UncheckedAccount<'info>,
+
     pub collateral_mint: InterfaceAccount<'info, Mint>,
 
     pub borrowed_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        seeds = [collateral_mint.key().as_ref()],
+        seeds = [b"bank", market.key().as_ref(), collateral_mint.key().as_ref()],
         bump
     )]
     pub collateral_bank: Account<'info, Bank>,
 
     #[account(
         mut,
-        seeds = [b"bank_token_account", collateral_mint.key().as_ref()],
+        seeds = [b"bank_token_account", market.key().as_ref(), collateral_mint.key().as_ref()],
         bump
     )]
     pub collateral_bank_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [borrowed_mint.key().as_ref()],
+        seeds = [b"receipt_mint", market.key().as_ref(), collateral_mint.key().as_ref()],
         bump
     )]
+    pub collateral_receipt_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"collateral_vault", market.key().as_ref(), collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"bank", market.key().as_ref(), borrowed_mint.key().as_ref()],
+        bump,
+        constraint = borrowed_bank.key() != collateral_bank.key() @ ErrorCode::InvalidCollateralBank,
+    )]
     pub borrowed_bank: Account<'info, Bank>,
 
     #[account(
         mut,
-        seeds = [b"bank_token_account", borrowed_mint.key().as_ref()],
+        seeds = [b"bank_token_account", market.key().as_ref(), borrowed_mint.key().as_ref()],
         bump
     )]
     pub borrowed_bank_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [liquidator.key().as_ref()],
+        seeds = [b"user", market.key().as_ref(), borrower.key().as_ref()],
         bump,
     )]
     pub user_account: Account<'info, User>,
@@ -60,7 +83,7 @@ pub struct Liquidate<'info> {
         payer = liquidator,
         associated_token::mint = collateral_mint,
         associated_token::authority = liquidator,
-        associated_token::token_program = token_program
+        associated_token::token_program = collateral_token_program
     )]
     pub liquidator_collateral_token_account: InterfaceAccount<'info, TokenAccount>,
 
@@ -69,105 +92,122 @@ pub struct Liquidate<'info> {
         payer = liquidator,
         associated_token::mint = borrowed_mint,
         associated_token::authority = liquidator,
-        associated_token::token_program = token_program
+        associated_token::token_program = borrowed_token_program
     )]
     pub liquidator_borrowed_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub price_update: Account<'info, PriceUpdateV2>,
+    /// CHECK: validated against the collateral bank's oracle config
+    pub collateral_price_update: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the collateral bank's oracle config
+    pub collateral_secondary_price_update: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: validated against the borrowed bank's oracle config
+    pub borrowed_price_update: UncheckedAccount<'info>,
+
+    /// CHECK: validated against the borrowed bank's oracle config
+    pub borrowed_secondary_price_update: Option<UncheckedAccount<'info>>,
 
     pub system_program: Program<'info, System>,
-    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub borrowed_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
-pub fn liquidate_handler(ctx: Context<Liquidate>) -> Result<()> {
+/// Repays up to `repay_amount` of the borrower's debt, capped by the borrowed
+/// bank's close factor, and reverts if the liquidator would receive less than
+/// `min_collateral_out` after the collateral mint's transfer fee.
+///
+/// The borrower is only liquidatable once their debt across every bank
+/// exceeds their liquidation limit. `remaining_accounts` must hold the bank
+/// and price accounts for each of the borrower's positions, in the layout
+/// expected by `compute_health`.
+pub fn liquidate_handler(
+    ctx: Context<Liquidate>,
+    repay_amount: u64,
+    min_collateral_out: u64,
+) -> Result<()> {
     let user = &mut ctx.accounts.user_account;
-    let price_update = &mut ctx.accounts.price_update;
     let collateral_bank = &mut ctx.accounts.collateral_bank;
     let borrowed_bank = &mut ctx.accounts.borrowed_bank;
-    let total_borrowed: u64;
-    let total_collateral: u64;
-
-    let sol_usd_feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID_HEX)?;
-    let sol_usd_price_response =
-        price_update.get_price_no_older_than(&Clock::get()?, MAX_AGE_PYTH, &sol_usd_feed_id)?;
-    let sol_usd_price = sol_usd_price_response.price;
-
-    let usdc_usd_feed_id = get_feed_id_from_hex(USDC_USD_FEED_ID_HEX)?;
-    let usdc_usd_price_response =
-        price_update.get_price_no_older_than(&Clock::get()?, MAX_AGE_PYTH, &usdc_usd_feed_id)?;
-    let usdc_usd_price = usdc_usd_price_response.price;
-
-    match ctx.accounts.collateral_mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            let collateral_accumulated_value = calculate_total_deposit_with_interest_accumulated(
-                user.last_updated,
-                user.deposited_usdc,
-                collateral_bank.interest_rate,
-            )?;
-            total_collateral = collateral_accumulated_value * usdc_usd_price as u64;
-
-            let borrowed_accumulated_value = calculate_total_deposit_with_interest_accumulated(
-                user.last_updated_borrowed,
-                user.borrowed_sol,
-                borrowed_bank.interest_rate,
-            )?;
-            total_borrowed = borrowed_accumulated_value * sol_usd_price as u64;
-        }
-        _ => {
-            let collateral_accumulated_value = calculate_total_deposit_with_interest_accumulated(
-                user.last_updated,
-                user.deposited_sol,
-                collateral_bank.interest_rate,
-            )?;
-            total_collateral = collateral_accumulated_value * sol_usd_price as u64;
-
-            let borrowed_accumulated_value = calculate_total_deposit_with_interest_accumulated(
-                user.last_updated_borrowed,
-                user.borrowed_usdc,
-                borrowed_bank.interest_rate,
-            )?;
-            total_borrowed = borrowed_accumulated_value * usdc_usd_price as u64;
-        }
-    }
 
-    // check whether the account falls below the health factor or not :-
+    let now = Clock::get()?.unix_timestamp;
+    collateral_bank.accrue_interest(now)?;
+    borrowed_bank.accrue_interest(now)?;
 
-    let health_factor = (total_collateral as f64 * collateral_bank.liquidation_threshold as f64)
-        / total_borrowed as f64;
+    let clock = Clock::get()?;
+    let collateral_price = get_oracle_price(
+        collateral_bank,
+        &ctx.accounts.collateral_price_update,
+        ctx.accounts.collateral_secondary_price_update.as_deref(),
+        &clock,
+    )?;
+    let borrowed_price = get_oracle_price(
+        borrowed_bank,
+        &ctx.accounts.borrowed_price_update,
+        ctx.accounts.borrowed_secondary_price_update.as_deref(),
+        &clock,
+    )?;
 
-    if health_factor >= 1.0 {
+    let collateral_shares = user
+        .position(&collateral_bank.key())
+        .map_or(0, |position| position.deposit_shares);
+    let borrowed_shares = user
+        .position(&borrowed_bank.key())
+        .map_or(0, |position| position.borrow_shares);
+
+    let collateral_accumulated_value =
+        collateral_bank.deposit_shares_to_amount(collateral_shares, Rounding::Down)?;
+    let borrowed_accumulated_value =
+        borrowed_bank.borrow_shares_to_amount(borrowed_shares, Rounding::Up)?;
+
+    // check whether the account falls below the health factor or not :-
+    let health = compute_health(
+        user,
+        ctx.remaining_accounts,
+        &[
+            (collateral_bank.key(), &**collateral_bank),
+            (borrowed_bank.key(), &**borrowed_bank),
+        ],
+        &clock,
+    )?;
+
+    if health.debt <= health.liquidation_limit {
         return Err(ErrorCode::DoesNotFallBelowHealthFactor.into());
     }
 
     // repaying the borrowed amount :-
-    let borrow_transfer_accounts = TransferChecked {
-        from: ctx
-            .accounts
-            .liquidator_borrowed_token_account
-            .to_account_info(),
-        mint: ctx.accounts.borrowed_mint.to_account_info(),
-        to: ctx.accounts.borrowed_bank_token_account.to_account_info(),
-        authority: ctx.accounts.liquidator.to_account_info(),
-    };
-
-    let borrow_transfer_cpi_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        borrow_transfer_accounts,
-    );
-    let liquidation_amount = total_borrowed
-        .checked_mul(borrowed_bank.liquidation_close_factor)
-        .unwrap();
-    transfer_checked(
-        borrow_transfer_cpi_ctx,
-        liquidation_amount,
-        ctx.accounts.borrowed_mint.decimals,
+    let max_liquidation_amount = Wad::from_bps(borrowed_bank.liquidation_close_factor)
+        .apply_to(borrowed_accumulated_value, Rounding::Down)?;
+    let liquidation_amount = transfer_in_measured(
+        &ctx.accounts.borrowed_token_program,
+        &ctx.accounts.liquidator_borrowed_token_account,
+        &ctx.accounts.borrowed_mint,
+        &mut ctx.accounts.borrowed_bank_token_account,
+        &ctx.accounts.liquidator,
+        repay_amount.min(max_liquidation_amount),
     )?;
 
     // transferring the collateral asset to the liquidator token account :-
 
-    let liquidator_amount_with_bonus =
-        liquidation_amount + (liquidation_amount * collateral_bank.liquidation_bonus);
+    // Converted at the reported prices so neither side profits from the
+    // confidence interval.
+    let repaid_value = borrowed_price.reference().usd_value(
+        liquidation_amount,
+        borrowed_bank.mint_decimals,
+        Rounding::Down,
+    )?;
+    let seized_value = repaid_value
+        .try_mul(
+            Wad::from_bps(collateral_bank.liquidation_bonus),
+            Rounding::Down,
+        )?
+        .try_add(repaid_value)?;
+    // The seizure is capped at the borrower's whole collateral balance.
+    let liquidator_amount_with_bonus = collateral_price
+        .reference()
+        .token_amount(seized_value, collateral_bank.mint_decimals, Rounding::Down)?
+        .min(collateral_accumulated_value);
 
     let collatoral_transfer_accounts = TransferChecked {
         from: ctx.accounts.collateral_bank_token_account.to_account_info(),
@@ -179,25 +219,102 @@ pub fn liquidate_handler(ctx: Context<Liquidate>) -> Result<()> {
         authority: ctx.accounts.collateral_bank_token_account.to_account_info(),
     };
 
+    let market_key = ctx.accounts.market.key();
     let collateral_mint_key = ctx.accounts.collateral_mint.key();
 
     let signer_seeds: &[&[&[u8]]] = &[&[
         b"bank_token_account",
+        market_key.as_ref(),
         collateral_mint_key.as_ref(),
         &[ctx.bumps.collateral_bank_token_account],
     ]];
 
     let collateral_cpi_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.collateral_token_program.to_account_info(),
         collatoral_transfer_accounts,
     )
     .with_signer(signer_seeds);
 
+    let collateral_balance_before = ctx.accounts.liquidator_collateral_token_account.amount;
     transfer_checked(
         collateral_cpi_ctx,
         liquidator_amount_with_bonus,
         ctx.accounts.collateral_mint.decimals,
     )?;
 
+    // The slippage bound applies to what the liquidator actually receives,
+    // net of any transfer fee on the collateral mint.
+    ctx.accounts.liquidator_collateral_token_account.reload()?;
+    let collateral_received = ctx
+        .accounts
+        .liquidator_collateral_token_account
+        .amount
+        .checked_sub(collateral_balance_before)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if collateral_received < min_collateral_out {
+        return Err(ErrorCode::LiquidationSlippageExceeded.into());
+    }
+
+    // states update :-
+
+    let repaid_shares = if liquidation_amount == borrowed_accumulated_value {
+        borrowed_shares
+    } else {
+        borrowed_bank.borrow_amount_to_shares(liquidation_amount, Rounding::Down)?
+    };
+    require!(repaid_shares > 0, ErrorCode::ZeroShares);
+    let seized_shares = if liquidator_amount_with_bonus == collateral_accumulated_value {
+        collateral_shares
+    } else {
+        collateral_bank
+            .deposit_amount_to_shares(liquidator_amount_with_bonus, Rounding::Up)?
+            .min(collateral_shares)
+    };
+
+    // burning the seized receipt tokens locked as collateral :-
+    let burn_accounts = Burn {
+        mint: ctx.accounts.collateral_receipt_mint.to_account_info(),
+        from: ctx.accounts.collateral_vault.to_account_info(),
+        authority: ctx.accounts.collateral_vault.to_account_info(),
+    };
+
+    let vault_signer_seeds: &[&[&[u8]]] = &[&[
+        b"collateral_vault",
+        market_key.as_ref(),
+        collateral_mint_key.as_ref(),
+        &[ctx.bumps.collateral_vault],
+    ]];
+
+    let burn_cpi_ctx = CpiContext::new(
+        ctx.accounts.collateral_token_program.to_account_info(),
+        burn_accounts,
+    )
+    .with_signer(vault_signer_seeds);
+    burn(burn_cpi_ctx, seized_shares)?;
+
+    let borrowed_position = user
+        .position_mut(&borrowed_bank.key())
+        .ok_or(ErrorCode::DoesNotFallBelowHealthFactor)?;
+    borrowed_position.borrow_shares -= repaid_shares;
+
+    let collateral_position = user
+        .position_mut(&collateral_bank.key())
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    collateral_position.deposit_shares -= seized_shares;
+
+    user.remove_empty_positions();
+
+    borrowed_bank.total_borrowed = borrowed_bank
+        .total_borrowed
+        .saturating_sub(liquidation_amount);
+    borrowed_bank.total_borrowed_shares -= repaid_shares;
+
+    collateral_bank.total_deposits = collateral_bank
+        .total_deposits
+        .saturating_sub(liquidator_amount_with_bonus);
+    collateral_bank.total_deposits_shares -= seized_shares;
+
+    collateral_bank.check_vault_solvency(&mut ctx.accounts.collateral_bank_token_account)?;
+
     Ok(())
 }
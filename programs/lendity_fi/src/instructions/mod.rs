@@ -7,6 +7,12 @@ pub use deposit::*;
 pub mod withdraw;
 pub use withdraw::*;
 
+pub mod deposit_collateral;
+pub use deposit_collateral::*;
+
+pub mod withdraw_collateral;
+pub use withdraw_collateral::*;
+
 pub mod borrow;
 pub use borrow::*;
 
@@ -15,3 +21,12 @@ pub use repay::*;
 
 pub mod liquidate;
 pub use liquidate::*;
+
+pub mod flash_borrow;
+pub use flash_borrow::*;
+
+pub mod flash_repay;
+pub use flash_repay::*;
+
+pub mod sync_bank;
+pub use sync_bank::*;
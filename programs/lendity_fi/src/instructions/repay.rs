@@ -1,30 +1,33 @@
-use std::f64::consts::E;
-
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::{error::ErrorCode, Bank, User};
+use crate::{
+    error::ErrorCode, gross_up_for_transfer_fee, math::Rounding, transfer_in_measured, Bank,
+    LendingMarket, User,
+};
 
 #[derive(Accounts)]
 pub struct Repay<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    pub market: Account<'info, LendingMarket>,
+
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        seeds = [mint.key().as_ref()],
+        seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank: Account<'info, Bank>,
 
     #[account(
         mut,
-        seeds = [signer.key().as_ref()],
+        seeds = [b"user", market.key().as_ref(), signer.key().as_ref()],
         bump,
     )]
     pub user_account: Account<'info, User>,
@@ -40,7 +43,7 @@ pub struct Repay<'info> {
 
     #[account(
         mut,
-        seeds = [b"bank_token_account", mint.key().as_ref()],
+        seeds = [b"bank_token_account", market.key().as_ref(), mint.key().as_ref()],
         bump
     )]
     pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
@@ -54,65 +57,49 @@ pub fn repay_handler(ctx: Context<Repay>, amount: u64) -> Result<()> {
     let user = &mut ctx.accounts.user_account;
     let bank = &mut ctx.accounts.bank;
 
-    let borrowed_value: u64;
-
-    if ctx.accounts.mint.to_account_info().key() == user.usdc_address {
-        borrowed_value = user.borrowed_usdc
-    } else {
-        borrowed_value = user.borrowed_sol
-    }
-
-    let time_diff = Clock::get()?.unix_timestamp - user.last_updated_borrowed;
-    let total_borrowed_with_interest_accumulated =
-        bank.total_borrowed as f64 * E.powf(bank.interest_rate * time_diff as f64);
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
 
-    let value_per_share =
-        total_borrowed_with_interest_accumulated / bank.total_borrowed_shares as f64;
+    let borrowed_shares = user
+        .position(&bank.key())
+        .map_or(0, |position| position.borrow_shares);
 
-    let user_accumulated_amount = borrowed_value as f64 / value_per_share;
+    let user_accumulated_amount = bank.borrow_shares_to_amount(borrowed_shares, Rounding::Up)?;
 
-    if amount as f64 > user_accumulated_amount {
+    // a fee-bearing mint may be sent the debt plus its transfer fee :-
+    let max_repay_amount = gross_up_for_transfer_fee(&ctx.accounts.mint, user_accumulated_amount)?;
+    if amount > max_repay_amount {
         return Err(ErrorCode::OverRepayAmount.into());
     }
 
     // transfer cpi:-
 
-    let repay_transfer_accounts = TransferChecked {
-        from: ctx.accounts.user_token_account.to_account_info(),
-        mint: ctx.accounts.mint.to_account_info(),
-        to: ctx.accounts.bank_token_account.to_account_info(),
-        authority: ctx.accounts.signer.to_account_info(),
-    };
-
-    let repay_transfer_cpi_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        repay_transfer_accounts,
-    );
-
-    transfer_checked(repay_transfer_cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+    let received = transfer_in_measured(
+        &ctx.accounts.token_program,
+        &ctx.accounts.user_token_account,
+        &ctx.accounts.mint,
+        &mut ctx.accounts.bank_token_account,
+        &ctx.accounts.signer,
+        amount,
+    )?;
 
     // states update :-
 
-    let borrow_ratio = amount
-        .checked_div(total_borrowed_with_interest_accumulated as u64)
-        .unwrap();
-    let user_shares = bank
-        .total_borrowed_shares
-        .checked_mul(borrow_ratio)
-        .unwrap();
-
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            user.borrowed_usdc -= amount;
-            user.borrowed_usdc_shares -= user_shares;
-        }
-        _ => {
-            user.borrowed_sol -= amount;
-            user.borrowed_sol_shares -= user_shares;
-        }
-    }
+    // Anything received past the debt is left to `sync_bank` as vault surplus.
+    let repaid = received.min(user_accumulated_amount);
+    let user_shares = if repaid == user_accumulated_amount {
+        borrowed_shares
+    } else {
+        bank.borrow_amount_to_shares(repaid, Rounding::Down)?
+    };
+    require!(user_shares > 0, ErrorCode::ZeroShares);
+
+    let position = user
+        .position_mut(&bank.key())
+        .ok_or(ErrorCode::OverRepayAmount)?;
+    position.borrow_shares -= user_shares;
+    user.remove_empty_positions();
 
-    bank.total_borrowed -= amount;
+    bank.total_borrowed = bank.total_borrowed.saturating_sub(repaid);
     bank.total_borrowed_shares -= user_shares;
 
     Ok(())
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::{
+    error::ErrorCode,
+    math::{Rounding, Wad},
+    Bank, LendingMarket,
+};
+
+#[derive(Accounts)]
+pub struct SyncBank<'info> {
+    pub market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+
+    #[account(
+        seeds = [b"bank_token_account", market.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[event]
+pub struct BankSynced {
+    pub bank: Pubkey,
+    pub vault_balance: u64,
+    pub required_vault_balance: u64,
+    pub protocol_share: u64,
+    pub depositor_share: u64,
+}
+
+/// Reconciles the books with the vault. Any surplus, such as donations or
+/// rounding dust, is split by the reserve factor between protocol fees and
+/// depositors. A shortfall is only reported.
+pub fn sync_bank_handler(ctx: Context<SyncBank>) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
+
+    let vault_balance = ctx.accounts.bank_token_account.amount;
+    let required_vault_balance = bank.required_vault_balance()?;
+    let surplus = vault_balance.saturating_sub(required_vault_balance);
+
+    let protocol_share = if bank.total_deposits_shares == 0 {
+        surplus
+    } else {
+        Wad::from_bps(bank.reserve_factor).apply_to(surplus, Rounding::Up)?
+    };
+    let depositor_share = surplus - protocol_share;
+
+    bank.accumulated_protocol_fees = bank
+        .accumulated_protocol_fees
+        .checked_add(protocol_share)
+        .ok_or(ErrorCode::MathOverflow)?;
+    bank.distribute_to_depositors(depositor_share)?;
+
+    emit!(BankSynced {
+        bank: bank.key(),
+        vault_balance,
+        required_vault_balance,
+        protocol_share,
+        depositor_share,
+    });
+
+    Ok(())
+}
@@ -1,34 +1,29 @@
-use std::f64::consts::E;
-
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token_interface::{
+        burn, transfer_checked, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
 };
 
-use crate::{error::ErrorCode, Bank, User};
+use crate::{error::ErrorCode, math::Rounding, Bank, LendingMarket};
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    pub market: Account<'info, LendingMarket>,
+
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
-        seeds = [mint.key().as_ref()],
+        seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
         bump,
     )]
     pub bank: Account<'info, Bank>,
 
-    #[account(
-        mut,
-        seeds = [signer.key().as_ref()],
-        bump,
-    )]
-    pub user_account: Account<'info, User>,
-
     #[account(
         init_if_needed,
         payer = signer,
@@ -40,41 +35,60 @@ pub struct Withdraw<'info> {
 
     #[account(
         mut,
-        seeds = [b"bank_token_account", mint.key().as_ref()],
+        seeds = [b"bank_token_account", market.key().as_ref(), mint.key().as_ref()],
         bump
     )]
     pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    #[account(
+        mut,
+        seeds = [b"receipt_mint", market.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program
+    )]
+    pub user_receipt_token_account: InterfaceAccount<'info, TokenAccount>,
+
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
+/// Redeems receipt tokens for `amount` of the underlying at the current
+/// supply index. Locked collateral must first be released with
+/// `withdraw_collateral`.
 pub fn withdraw_handler(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
-    let user = &mut ctx.accounts.user_account;
     let bank = &mut ctx.accounts.bank;
 
-    let deposited_amount: u64;
-    if ctx.accounts.mint.to_account_info().key() == user.usdc_address {
-        deposited_amount = user.deposited_usdc;
-    } else {
-        deposited_amount = user.deposited_sol;
-    }
-
-    let time_diff = user.last_updated - Clock::get()?.unix_timestamp;
-    let total_deposit = bank.total_deposits as f64 * E.powf(bank.interest_rate * time_diff as f64);
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
 
-    let value_per_share = total_deposit / (bank.total_deposits_shares as f64);
+    let receipt_shares = ctx.accounts.user_receipt_token_account.amount;
 
-    let user_accumulated_amount = (deposited_amount as f64) / value_per_share;
+    let user_accumulated_amount = bank.deposit_shares_to_amount(receipt_shares, Rounding::Down)?;
 
-    if user_accumulated_amount < (amount as f64) {
+    if user_accumulated_amount < amount {
         return Err(ErrorCode::InsufficientFunds.into());
     }
 
-    if amount > deposited_amount {
-        return Err(ErrorCode::InsufficientFunds.into());
-    }
+    let shares_to_remove = bank
+        .deposit_amount_to_shares(amount, Rounding::Up)?
+        .min(receipt_shares);
+
+    // burning the receipt tokens :-
+    let burn_accounts = Burn {
+        mint: ctx.accounts.receipt_mint.to_account_info(),
+        from: ctx.accounts.user_receipt_token_account.to_account_info(),
+        authority: ctx.accounts.signer.to_account_info(),
+    };
+
+    let burn_cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), burn_accounts);
+    burn(burn_cpi_ctx, shares_to_remove)?;
 
     let withdraw_transfer_accounts = TransferChecked {
         from: ctx.accounts.bank_token_account.to_account_info(),
@@ -83,11 +97,13 @@ pub fn withdraw_handler(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         authority: ctx.accounts.bank_token_account.to_account_info(),
     };
 
-    // seeds = [b"bank_token_account", mint.key().as_ref()],
+    // seeds = [b"bank_token_account", market.key().as_ref(), mint.key().as_ref()],
+    let market_key = ctx.accounts.market.key();
     let mint_key = ctx.accounts.mint.key();
 
     let signer_seeds: &[&[&[u8]]] = &[&[
         b"bank_token_account",
+        market_key.as_ref(),
         mint_key.as_ref(),
         &[ctx.bumps.bank_token_account],
     ]];
@@ -100,21 +116,10 @@ pub fn withdraw_handler(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
 
     transfer_checked(withdraw_cpi_ctx, amount, ctx.accounts.mint.decimals)?;
 
-    let shares_to_remove = (amount / bank.total_deposits) * bank.total_deposits_shares;
-
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            user.deposited_usdc -= amount;
-            user.deposited_usdc_shares -= shares_to_remove;
-        }
-        _ => {
-            user.deposited_sol -= amount;
-            user.deposited_sol_shares -= shares_to_remove;
-        }
-    }
-
-    bank.total_deposits -= amount;
+    bank.total_deposits = bank.total_deposits.saturating_sub(amount);
     bank.total_deposits_shares -= shares_to_remove;
 
+    bank.check_vault_solvency(&mut ctx.accounts.bank_token_account)?;
+
     Ok(())
 }
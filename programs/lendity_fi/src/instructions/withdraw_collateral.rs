@@ -0,0 +1,118 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{error::ErrorCode, health::compute_health, Bank, LendingMarket, User};
+
+#[derive(Accounts)]
+pub struct WithdrawCollateral<'info> {
+    pub signer: Signer<'info>,
+
+    pub market: Account<'info, LendingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"bank", market.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub bank: Account<'info, Bank>,
+
+    #[account(
+        mut,
+        seeds = [b"user", market.key().as_ref(), signer.key().as_ref()],
+        bump,
+    )]
+    pub user_account: Account<'info, User>,
+
+    #[account(
+        seeds = [b"receipt_mint", market.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = signer,
+        token::token_program = token_program,
+    )]
+    pub user_receipt_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"collateral_vault", market.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Releases `shares` locked receipt tokens back to the user. When the user has
+/// outstanding borrows, remaining accounts carry the bank and price accounts of
+/// every position held before the release, in the layout expected by
+/// `compute_health`.
+pub fn withdraw_collateral_handler(ctx: Context<WithdrawCollateral>, shares: u64) -> Result<()> {
+    let user = &mut ctx.accounts.user_account;
+    let bank = &mut ctx.accounts.bank;
+
+    require!(shares > 0, ErrorCode::ZeroShares);
+
+    bank.accrue_interest(Clock::get()?.unix_timestamp)?;
+
+    let position = user
+        .position_mut(&bank.key())
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    if position.deposit_shares < shares {
+        return Err(ErrorCode::InsufficientFunds.into());
+    }
+    position.deposit_shares -= shares;
+
+    // health check on the remaining collateral :-
+    let has_debt = user
+        .positions
+        .iter()
+        .any(|position| position.borrow_shares > 0);
+    if has_debt {
+        let health = compute_health(
+            user,
+            ctx.remaining_accounts,
+            &[(bank.key(), &**bank)],
+            &Clock::get()?,
+        )?;
+        if health.debt > health.borrow_limit {
+            return Err(ErrorCode::WithdrawalLeavesUnhealthyAccount.into());
+        }
+    }
+
+    user.remove_empty_positions();
+
+    let unlock_transfer_accounts = TransferChecked {
+        from: ctx.accounts.collateral_vault.to_account_info(),
+        mint: ctx.accounts.receipt_mint.to_account_info(),
+        to: ctx.accounts.user_receipt_token_account.to_account_info(),
+        authority: ctx.accounts.collateral_vault.to_account_info(),
+    };
+
+    let market_key = ctx.accounts.market.key();
+    let mint_key = ctx.accounts.mint.key();
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"collateral_vault",
+        market_key.as_ref(),
+        mint_key.as_ref(),
+        &[ctx.bumps.collateral_vault],
+    ]];
+
+    let unlock_cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        unlock_transfer_accounts,
+    )
+    .with_signer(signer_seeds);
+    transfer_checked(unlock_cpi_ctx, shares, ctx.accounts.receipt_mint.decimals)?;
+
+    Ok(())
+}
@@ -1,6 +1,9 @@
 pub mod constants;
 pub mod error;
+pub mod health;
 pub mod instructions;
+pub mod math;
+pub mod oracle;
 pub mod state;
 
 use anchor_lang::prelude::*;
@@ -15,17 +18,18 @@ declare_id!("BUmmLcAocQGUHYn96oMpGJSX6xXGtG22pZNHNNB79C94");
 pub mod lendity_fi {
     use super::*;
 
-    pub fn initialize_bank(
-        ctx: Context<InitializeBank>,
-        liquidation_threshold: u64,
-        max_ltv: u64,
-    ) -> Result<()> {
-        initialize_bank_handler(ctx, liquidation_threshold, max_ltv)?;
+    pub fn initialize_market(ctx: Context<InitializeMarket>) -> Result<()> {
+        initialize_market_handler(ctx)?;
+        Ok(())
+    }
+
+    pub fn initialize_bank(ctx: Context<InitializeBank>, config: BankConfig) -> Result<()> {
+        initialize_bank_handler(ctx, config)?;
         Ok(())
     }
 
-    pub fn initialize_user(ctx: Context<InitializeUser>, usdc_address: Pubkey) -> Result<()> {
-        initialize_user_handler(ctx, usdc_address)?;
+    pub fn initialize_user(ctx: Context<InitializeUser>) -> Result<()> {
+        initialize_user_handler(ctx)?;
         Ok(())
     }
 
@@ -39,6 +43,16 @@ pub mod lendity_fi {
         Ok(())
     }
 
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, shares: u64) -> Result<()> {
+        deposit_collateral_handler(ctx, shares)?;
+        Ok(())
+    }
+
+    pub fn withdraw_collateral(ctx: Context<WithdrawCollateral>, shares: u64) -> Result<()> {
+        withdraw_collateral_handler(ctx, shares)?;
+        Ok(())
+    }
+
     pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
         borrow_handler(ctx, amount)?;
         Ok(())
@@ -49,8 +63,47 @@ pub mod lendity_fi {
         Ok(())
     }
 
-    pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
-        liquidate_handler(ctx)?;
+    pub fn liquidate(
+        ctx: Context<Liquidate>,
+        repay_amount: u64,
+        min_collateral_out: u64,
+    ) -> Result<()> {
+        liquidate_handler(ctx, repay_amount, min_collateral_out)?;
+        Ok(())
+    }
+
+    pub fn withdraw_protocol_fees(ctx: Context<WithdrawProtocolFees>, amount: u64) -> Result<()> {
+        withdraw_protocol_fees_handler(ctx, amount)?;
+        Ok(())
+    }
+
+    pub fn update_bank_config(ctx: Context<UpdateBankConfig>, config: BankConfig) -> Result<()> {
+        update_bank_config_handler(ctx, config)?;
+        Ok(())
+    }
+
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        propose_authority_handler(ctx, new_authority)?;
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        accept_authority_handler(ctx)?;
+        Ok(())
+    }
+
+    pub fn flash_borrow(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+        flash_borrow_handler(ctx, amount)?;
+        Ok(())
+    }
+
+    pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+        flash_repay_handler(ctx)?;
+        Ok(())
+    }
+
+    pub fn sync_bank(ctx: Context<SyncBank>) -> Result<()> {
+        sync_bank_handler(ctx)?;
         Ok(())
     }
 }
@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+use self::uint_types::U256;
+
+#[allow(clippy::all)]
+mod uint_types {
+    // Kept out of the parent module so the macro does not pick up Anchor's
+    // `Result` alias from the prelude.
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+/// Scale of the `Wad` fixed-point type (18 decimals).
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+
+/// Denominator for values expressed in basis points.
+pub const BPS: u64 = 10_000;
+
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// Unsigned fixed-point number with 18 decimals of precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Wad(pub u128);
+
+impl Wad {
+    pub const ZERO: Wad = Wad(0);
+    pub const ONE: Wad = Wad(WAD);
+
+    pub fn from_bps(bps: u64) -> Self {
+        Wad(bps as u128 * (WAD / BPS as u128))
+    }
+
+    pub fn from_ratio(numerator: u128, denominator: u128, rounding: Rounding) -> Result<Self> {
+        Ok(Wad(mul_div(numerator, WAD, denominator, rounding)?))
+    }
+
+    pub fn try_add(self, rhs: Wad) -> Result<Self> {
+        self.0
+            .checked_add(rhs.0)
+            .map(Wad)
+            .ok_or_else(|| ErrorCode::MathOverflow.into())
+    }
+
+    pub fn try_sub(self, rhs: Wad) -> Result<Self> {
+        self.0
+            .checked_sub(rhs.0)
+            .map(Wad)
+            .ok_or_else(|| ErrorCode::MathOverflow.into())
+    }
+
+    pub fn try_mul(self, rhs: Wad, rounding: Rounding) -> Result<Self> {
+        Ok(Wad(mul_div(self.0, rhs.0, WAD, rounding)?))
+    }
+
+    pub fn try_div(self, rhs: Wad, rounding: Rounding) -> Result<Self> {
+        Ok(Wad(mul_div(self.0, WAD, rhs.0, rounding)?))
+    }
+
+    /// Multiplies a raw token amount by this value, e.g. shares by an index.
+    pub fn apply_to(self, amount: u64, rounding: Rounding) -> Result<u64> {
+        to_u64(mul_div(amount as u128, self.0, WAD, rounding)?)
+    }
+
+    /// Divides a raw token amount by this value, e.g. an amount by an index.
+    pub fn divide(self, amount: u64, rounding: Rounding) -> Result<u64> {
+        to_u64(mul_div(amount as u128, WAD, self.0, rounding)?)
+    }
+
+    /// Growth factor of continuous compounding at the annual `rate` over
+    /// `elapsed` seconds, approximated by the first four terms of the
+    /// Taylor series of `e^x`. Rounds down so accrual never overshoots.
+    pub fn compound(rate: Wad, elapsed: u64) -> Result<Self> {
+        let x = Wad(mul_div(
+            rate.0,
+            elapsed as u128,
+            SECONDS_PER_YEAR as u128,
+            Rounding::Down,
+        )?);
+        let x_squared = x.try_mul(x, Rounding::Down)?;
+        let x_cubed = x_squared.try_mul(x, Rounding::Down)?;
+
+        Wad::ONE
+            .try_add(x)?
+            .try_add(Wad(x_squared.0 / 2))?
+            .try_add(Wad(x_cubed.0 / 6))
+    }
+}
+
+/// Computes `a * b / denominator` with a 256-bit intermediate product.
+pub fn mul_div(a: u128, b: u128, denominator: u128, rounding: Rounding) -> Result<u128> {
+    require!(denominator != 0, ErrorCode::MathOverflow);
+
+    let product = U256::from(a) * U256::from(b);
+    let denominator = U256::from(denominator);
+    let (quotient, remainder) = product.div_mod(denominator);
+    let quotient = if rounding == Rounding::Up && !remainder.is_zero() {
+        quotient + 1
+    } else {
+        quotient
+    };
+
+    require!(quotient <= U256::from(u128::MAX), ErrorCode::MathOverflow);
+    Ok(quotient.as_u128())
+}
+
+fn to_u64(value: u128) -> Result<u64> {
+    u64::try_from(value).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_rounds_remainders() {
+        assert_eq!(mul_div(7, 1, 3, Rounding::Down).unwrap(), 2);
+        assert_eq!(mul_div(7, 1, 3, Rounding::Up).unwrap(), 3);
+        assert_eq!(mul_div(6, 1, 3, Rounding::Down).unwrap(), 2);
+        assert_eq!(mul_div(6, 1, 3, Rounding::Up).unwrap(), 2);
+    }
+
+    #[test]
+    fn mul_div_uses_a_wide_intermediate() {
+        assert_eq!(
+            mul_div(u128::MAX, u128::MAX, u128::MAX, Rounding::Down).unwrap(),
+            u128::MAX
+        );
+    }
+
+    #[test]
+    fn mul_div_rejects_results_past_u128() {
+        assert!(mul_div(u128::MAX, 2, 1, Rounding::Down).is_err());
+        assert!(mul_div(u128::MAX, u128::MAX, u128::MAX - 1, Rounding::Up).is_err());
+    }
+
+    #[test]
+    fn mul_div_rejects_zero_denominator() {
+        assert!(mul_div(1, 1, 0, Rounding::Down).is_err());
+        assert!(Wad::from_ratio(1, 0, Rounding::Down).is_err());
+    }
+
+    #[test]
+    fn from_ratio_rounds() {
+        assert_eq!(
+            Wad::from_ratio(1, 3, Rounding::Down).unwrap(),
+            Wad(333_333_333_333_333_333)
+        );
+        assert_eq!(
+            Wad::from_ratio(1, 3, Rounding::Up).unwrap(),
+            Wad(333_333_333_333_333_334)
+        );
+        assert_eq!(Wad::from_ratio(5, 5, Rounding::Up).unwrap(), Wad::ONE);
+    }
+
+    #[test]
+    fn compound_matches_known_values() {
+        assert_eq!(Wad::compound(Wad::from_bps(500), 0).unwrap(), Wad::ONE);
+        assert_eq!(
+            Wad::compound(Wad::ZERO, SECONDS_PER_YEAR).unwrap(),
+            Wad::ONE
+        );
+
+        // 1 + 1 + 1/2 + 1/6
+        assert_eq!(
+            Wad::compound(Wad::ONE, SECONDS_PER_YEAR).unwrap(),
+            Wad(2_666_666_666_666_666_666)
+        );
+        // 10% over half a year: x = 0.05
+        assert_eq!(
+            Wad::compound(Wad::from_bps(1_000), SECONDS_PER_YEAR / 2).unwrap(),
+            Wad(1_051_270_833_333_333_333)
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::{error::GetPriceError, price_update::PriceUpdateV2};
+
+use crate::{
+    error::ErrorCode,
+    math::{mul_div, Rounding, Wad, BPS, WAD},
+    Bank, OracleType, PriceFeed, PriceMode,
+};
+
+/// Spot and EMA prices of a bank's asset, valued according to the bank's
+/// price mode.
+#[derive(Clone, Copy, Debug)]
+pub struct OraclePrice {
+    pub spot: PriceQuote,
+    pub ema: PriceQuote,
+    pub mode: PriceMode,
+}
+
+impl OraclePrice {
+    /// Price used to value collateral: the selected quote minus its
+    /// confidence interval, or the lower of both in conservative mode.
+    pub fn lower_bound(&self) -> Result<PriceQuote> {
+        match self.mode {
+            PriceMode::Spot => self.spot.lower_bound(),
+            PriceMode::Ema => self.ema.lower_bound(),
+            PriceMode::Conservative => {
+                let spot = self.spot.lower_bound()?;
+                let ema = self.ema.lower_bound()?;
+                Ok(if spot.price <= ema.price { spot } else { ema })
+            }
+        }
+    }
+
+    /// Price used to value debt: the selected quote plus its confidence
+    /// interval, or the higher of both in conservative mode.
+    pub fn upper_bound(&self) -> Result<PriceQuote> {
+        match self.mode {
+            PriceMode::Spot => self.spot.upper_bound(),
+            PriceMode::Ema => self.ema.upper_bound(),
+            PriceMode::Conservative => {
+                let spot = self.spot.upper_bound()?;
+                let ema = self.ema.upper_bound()?;
+                Ok(if spot.price >= ema.price { spot } else { ema })
+            }
+        }
+    }
+
+    /// Unadjusted price used to convert between assets.
+    pub fn reference(&self) -> PriceQuote {
+        match self.mode {
+            PriceMode::Ema => self.ema,
+            PriceMode::Spot | PriceMode::Conservative => self.spot,
+        }
+    }
+}
+
+/// A single oracle price, in USD as `price * 10^exponent`.
+#[derive(Clone, Copy, Debug)]
+pub struct PriceQuote {
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+}
+
+impl PriceQuote {
+    fn lower_bound(&self) -> Result<Self> {
+        let price = self
+            .price
+            .checked_sub(self.conf as i64)
+            .filter(|price| *price > 0)
+            .ok_or(ErrorCode::InvalidPrice)?;
+        Ok(PriceQuote { price, ..*self })
+    }
+
+    fn upper_bound(&self) -> Result<Self> {
+        let price = self
+            .price
+            .checked_add(self.conf as i64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(PriceQuote { price, ..*self })
+    }
+
+    /// USD value of `amount` base units of a token with `decimals` decimals.
+    pub fn usd_value(&self, amount: u64, decimals: u8, rounding: Rounding) -> Result<Wad> {
+        let value = (amount as u128)
+            .checked_mul(self.unsigned_price()?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let scale = self.exponent - decimals as i32;
+
+        if scale >= 0 {
+            let multiplier = pow10(scale)?
+                .checked_mul(WAD)
+                .ok_or(ErrorCode::MathOverflow)?;
+            Ok(Wad(mul_div(value, multiplier, 1, rounding)?))
+        } else {
+            Ok(Wad(mul_div(value, WAD, pow10(-scale)?, rounding)?))
+        }
+    }
+
+    /// Number of base units of a token with `decimals` decimals worth `value`
+    /// USD. Inverse of [`PriceQuote::usd_value`].
+    pub fn token_amount(&self, value: Wad, decimals: u8, rounding: Rounding) -> Result<u64> {
+        let price = self
+            .unsigned_price()?
+            .checked_mul(WAD)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let scale = decimals as i32 - self.exponent;
+
+        let amount = if scale >= 0 {
+            mul_div(value.0, pow10(scale)?, price, rounding)?
+        } else {
+            let denominator = price
+                .checked_mul(pow10(-scale)?)
+                .ok_or(ErrorCode::MathOverflow)?;
+            mul_div(value.0, 1, denominator, rounding)?
+        };
+        u64::try_from(amount).map_err(|_| ErrorCode::MathOverflow.into())
+    }
+
+    fn unsigned_price(&self) -> Result<u128> {
+        u128::try_from(self.price).map_err(|_| ErrorCode::InvalidPrice.into())
+    }
+}
+
+fn pow10(exponent: i32) -> Result<u128> {
+    10u128
+        .checked_pow(exponent as u32)
+        .ok_or_else(|| ErrorCode::MathOverflow.into())
+}
+
+/// Reads the price of the bank's asset, checking the accounts against the
+/// bank's oracle configuration. Falls back to the secondary source when the
+/// primary is stale and refuses to price when both are fresh but disagree by
+/// more than `max_deviation_bps`.
+pub fn get_oracle_price(
+    bank: &Bank,
+    price_account: &AccountInfo,
+    secondary_price_account: Option<&AccountInfo>,
+    clock: &Clock,
+) -> Result<OraclePrice> {
+    let primary = get_primary_price(bank, price_account, clock)?;
+
+    let secondary = if bank.oracle.has_secondary_oracle() {
+        let secondary_price_account =
+            secondary_price_account.ok_or(ErrorCode::InvalidOracleAccount)?;
+        get_secondary_price(bank, secondary_price_account, clock)?
+    } else {
+        None
+    };
+
+    match (primary, secondary) {
+        (Some(primary), Some(secondary)) => {
+            // Compare the quotes the bank actually prices with, so an EMA
+            // priced bank is checked on its EMA.
+            check_deviation(bank, &primary.reference(), &secondary.reference())?;
+            Ok(primary)
+        }
+        (Some(price), None) | (None, Some(price)) => Ok(price),
+        (None, None) => Err(ErrorCode::StalePrice.into()),
+    }
+}
+
+/// Returns `None` when the primary price is older than the bank's `max_age`.
+fn get_primary_price(
+    bank: &Bank,
+    price_account: &AccountInfo,
+    clock: &Clock,
+) -> Result<Option<OraclePrice>> {
+    match bank.oracle.oracle_type {
+        OracleType::Pyth => {
+            require_keys_eq!(
+                *price_account.owner,
+                PriceUpdateV2::owner(),
+                ErrorCode::InvalidOracleAccount
+            );
+            let price_update =
+                PriceUpdateV2::try_deserialize(&mut &price_account.try_borrow_data()?[..])?;
+            let price = match price_update.get_price_no_older_than(
+                clock,
+                bank.oracle.max_age,
+                &bank.oracle.feed_id,
+            ) {
+                Ok(price) => price,
+                Err(GetPriceError::PriceTooOld) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+
+            let spot = PriceQuote {
+                price: price.price,
+                conf: price.conf,
+                exponent: price.exponent,
+            };
+            let ema = PriceQuote {
+                price: price_update.price_message.ema_price,
+                conf: price_update.price_message.ema_conf,
+                exponent: price.exponent,
+            };
+
+            validate_price(bank, spot, ema).map(Some)
+        }
+    }
+}
+
+/// Returns `None` when the secondary price is older than the bank's
+/// `max_age`. The feed carries no EMA, so its quote serves for both.
+fn get_secondary_price(
+    bank: &Bank,
+    price_account: &AccountInfo,
+    clock: &Clock,
+) -> Result<Option<OraclePrice>> {
+    require_keys_eq!(
+        price_account.key(),
+        bank.oracle.secondary_oracle,
+        ErrorCode::InvalidOracleAccount
+    );
+    let price_feed = PriceFeed::try_deserialize(&mut &price_account.try_borrow_data()?[..])?;
+
+    let max_age = i64::try_from(bank.oracle.max_age).map_err(|_| ErrorCode::MathOverflow)?;
+    if price_feed.publish_time.saturating_add(max_age) < clock.unix_timestamp {
+        return Ok(None);
+    }
+
+    let quote = PriceQuote {
+        price: price_feed.price,
+        conf: price_feed.conf,
+        exponent: price_feed.exponent,
+    };
+
+    validate_price(bank, quote, quote).map(Some)
+}
+
+fn check_deviation(bank: &Bank, primary: &PriceQuote, secondary: &PriceQuote) -> Result<()> {
+    let primary_value = primary.usd_value(1, 0, Rounding::Down)?;
+    let secondary_value = secondary.usd_value(1, 0, Rounding::Down)?;
+    let difference = if primary_value > secondary_value {
+        primary_value.try_sub(secondary_value)?
+    } else {
+        secondary_value.try_sub(primary_value)?
+    };
+
+    let max_difference =
+        primary_value.try_mul(Wad::from_bps(bank.oracle.max_deviation_bps), Rounding::Down)?;
+    require!(
+        difference <= max_difference,
+        ErrorCode::PriceDeviationTooHigh
+    );
+
+    Ok(())
+}
+
+/// Rejects non-positive prices and prices whose confidence interval exceeds
+/// the bank's configured ratio. The EMA quote is only checked when the bank's
+/// price mode uses it.
+fn validate_price(bank: &Bank, spot: PriceQuote, ema: PriceQuote) -> Result<OraclePrice> {
+    let mode = bank.oracle.price_mode;
+
+    validate_quote(bank, &spot)?;
+    if mode != PriceMode::Spot {
+        validate_quote(bank, &ema)?;
+    }
+
+    Ok(OraclePrice { spot, ema, mode })
+}
+
+fn validate_quote(bank: &Bank, quote: &PriceQuote) -> Result<()> {
+    require!(quote.price > 0, ErrorCode::InvalidPrice);
+
+    let max_conf = (quote.price as u128) * bank.oracle.max_confidence_bps as u128;
+    require!(
+        (quote.conf as u128) * (BPS as u128) <= max_conf,
+        ErrorCode::PriceTooUncertain
+    );
+
+    Ok(())
+}
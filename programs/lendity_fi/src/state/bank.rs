@@ -1,18 +1,681 @@
 use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+        },
+    },
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    error::ErrorCode,
+    math::{Rounding, Wad, BPS},
+    MAX_BORROW_RATE_BPS, MIN_VIRTUAL_DEPOSIT_SHARES, VIRTUAL_DEPOSIT_SHARES_DIVISOR,
+};
 
 #[account]
 #[derive(InitSpace)]
 pub struct Bank {
+    pub market: Pubkey,
     pub authority: Pubkey,
+    /// Authority proposed by `propose_authority`, or the default key when no
+    /// transfer is in progress.
+    pub pending_authority: Pubkey,
     pub mint_address: Pubkey,
+    pub mint_decimals: u8,
     pub total_deposits: u64,
     pub total_deposits_shares: u64,
+    /// Shares added to the deposit share supply when distributing interest,
+    /// so a near-empty bank cannot have its share price inflated. Scaled to
+    /// the mint's decimals at creation.
+    pub virtual_deposit_shares: u64,
+    /// Payouts owed to the virtual deposit shares. Counted by
+    /// `required_vault_balance` so they are never paid out again.
+    pub virtual_reserve: u64,
     pub total_borrowed: u64,
     pub total_borrowed_shares: u64,
+    /// Risk parameters, all in basis points. See `RiskParams`.
     pub liquidation_threshold: u64,
     pub liquidation_bonus: u64,
     pub liquidation_close_factor: u64,
     pub max_ltv: u64,
     pub last_updated: i64,
-    pub interest_rate: f64,
+    pub interest_rate_model: InterestRateModel,
+    /// Portion of borrow interest kept by the protocol, in basis points.
+    pub reserve_factor: u64,
+    /// Protocol fees held in the bank token account, owed to the treasury.
+    pub accumulated_protocol_fees: u64,
+    pub oracle: OracleConfig,
+    /// Maximum total deposits in tokens, or zero for no cap.
+    pub deposit_cap: u64,
+    /// Maximum total borrows in tokens, or zero for no cap.
+    pub borrow_cap: u64,
+    /// Fee charged on flash loans, in basis points, paid to depositors.
+    pub flash_loan_fee_bps: u64,
+    /// Principal of the flash loan open in the current transaction, if any.
+    pub flash_loan_amount: u64,
+    /// Cumulative supply index (WAD) converting deposit shares into tokens.
+    pub supply_index: u128,
+    /// Cumulative borrow index (WAD) converting borrow shares into tokens.
+    pub borrow_index: u128,
+}
+
+impl Bank {
+    /// Advances both indices from `last_updated` to `now` and refreshes the
+    /// token totals derived from them.
+    pub fn accrue_interest(&mut self, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.last_updated);
+        if elapsed <= 0 {
+            return Ok(());
+        }
+
+        let borrow_rate = self.interest_rate_model.borrow_rate(self.utilization()?)?;
+        let growth = Wad::compound(borrow_rate, elapsed as u64)?;
+        self.borrow_index = Wad(self.borrow_index).try_mul(growth, Rounding::Up)?.0;
+
+        let previous_borrowed = self.total_borrowed;
+        self.total_borrowed =
+            self.borrow_shares_to_amount(self.total_borrowed_shares, Rounding::Up)?;
+        let interest = self.total_borrowed.saturating_sub(previous_borrowed);
+        let protocol_fee = Wad::from_bps(self.reserve_factor).apply_to(interest, Rounding::Up)?;
+        let depositor_interest = interest - protocol_fee;
+
+        self.accumulated_protocol_fees = self
+            .accumulated_protocol_fees
+            .checked_add(protocol_fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Depositors receive the interest borrowers paid minus the protocol
+        // fee, which makes the effective supply rate
+        // `borrow_rate * utilization * (1 - reserve_factor)`.
+        self.distribute_to_depositors(depositor_interest)?;
+
+        self.last_updated = now;
+
+        Ok(())
+    }
+
+    /// Virtual deposit shares for a mint with `mint_decimals`: a thousandth
+    /// of a whole token, but never less than `MIN_VIRTUAL_DEPOSIT_SHARES`.
+    pub fn virtual_deposit_shares_for(mint_decimals: u8) -> Result<u64> {
+        let one_token = 10u64
+            .checked_pow(mint_decimals as u32)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok((one_token / VIRTUAL_DEPOSIT_SHARES_DIVISOR).max(MIN_VIRTUAL_DEPOSIT_SHARES))
+    }
+
+    /// Credits `amount` tokens to depositors by raising the supply index.
+    pub fn distribute_to_depositors(&mut self, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        if self.total_deposits_shares == 0 {
+            return self.add_virtual_reserve(amount);
+        }
+
+        let previous_deposits = self.total_deposits;
+        let index_increase = Wad::from_ratio(
+            amount as u128,
+            self.total_deposits_shares as u128 + self.virtual_deposit_shares as u128,
+            Rounding::Down,
+        )?;
+        self.supply_index = Wad(self.supply_index).try_add(index_increase)?.0;
+        self.total_deposits =
+            self.deposit_shares_to_amount(self.total_deposits_shares, Rounding::Down)?;
+
+        // The part owed to the virtual shares, and any rounding dust, stays
+        // booked in the vault so `sync_bank` cannot redistribute it.
+        let credited = self.total_deposits.saturating_sub(previous_deposits);
+        self.add_virtual_reserve(amount.saturating_sub(credited))
+    }
+
+    fn add_virtual_reserve(&mut self, amount: u64) -> Result<()> {
+        self.virtual_reserve = self
+            .virtual_reserve
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Share of deposits currently lent out, capped at 100%.
+    pub fn utilization(&self) -> Result<Wad> {
+        if self.total_deposits == 0 {
+            return Ok(Wad::ZERO);
+        }
+
+        let utilization = Wad::from_ratio(
+            self.total_borrowed as u128,
+            self.total_deposits as u128,
+            Rounding::Down,
+        )?;
+        Ok(utilization.min(Wad::ONE))
+    }
+
+    pub fn config(&self) -> BankConfig {
+        BankConfig {
+            risk_params: RiskParams {
+                max_ltv: self.max_ltv,
+                liquidation_threshold: self.liquidation_threshold,
+                liquidation_bonus: self.liquidation_bonus,
+                liquidation_close_factor: self.liquidation_close_factor,
+            },
+            interest_rate_model: self.interest_rate_model,
+            reserve_factor: self.reserve_factor,
+            deposit_cap: self.deposit_cap,
+            borrow_cap: self.borrow_cap,
+            flash_loan_fee_bps: self.flash_loan_fee_bps,
+            oracle: self.oracle,
+        }
+    }
+
+    /// Applies an already validated config.
+    pub fn set_config(&mut self, config: BankConfig) {
+        self.max_ltv = config.risk_params.max_ltv;
+        self.liquidation_threshold = config.risk_params.liquidation_threshold;
+        self.liquidation_bonus = config.risk_params.liquidation_bonus;
+        self.liquidation_close_factor = config.risk_params.liquidation_close_factor;
+        self.interest_rate_model = config.interest_rate_model;
+        self.reserve_factor = config.reserve_factor;
+        self.deposit_cap = config.deposit_cap;
+        self.borrow_cap = config.borrow_cap;
+        self.flash_loan_fee_bps = config.flash_loan_fee_bps;
+        self.oracle = config.oracle;
+    }
+
+    /// Tokens the vault must hold to cover deposits, protocol fees and the
+    /// virtual reserve, net of outstanding borrows and any open flash loan.
+    pub fn required_vault_balance(&self) -> Result<u64> {
+        let lent_out = self
+            .total_borrowed
+            .checked_add(self.flash_loan_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(self
+            .total_deposits
+            .checked_add(self.accumulated_protocol_fees)
+            .and_then(|required| required.checked_add(self.virtual_reserve))
+            .ok_or(ErrorCode::MathOverflow)?
+            .saturating_sub(lent_out))
+    }
+
+    /// Reloads the vault after an outflow and checks it still covers the
+    /// books.
+    pub fn check_vault_solvency(&self, vault: &mut InterfaceAccount<TokenAccount>) -> Result<()> {
+        vault.reload()?;
+        require!(
+            vault.amount >= self.required_vault_balance()?,
+            ErrorCode::VaultInsolvent
+        );
+        Ok(())
+    }
+
+    pub fn check_deposit_cap(&self) -> Result<()> {
+        require!(
+            self.deposit_cap == 0 || self.total_deposits <= self.deposit_cap,
+            ErrorCode::DepositCapExceeded
+        );
+        Ok(())
+    }
+
+    pub fn check_borrow_cap(&self) -> Result<()> {
+        require!(
+            self.borrow_cap == 0 || self.total_borrowed <= self.borrow_cap,
+            ErrorCode::BorrowCapExceeded
+        );
+        Ok(())
+    }
+
+    pub fn deposit_shares_to_amount(&self, shares: u64, rounding: Rounding) -> Result<u64> {
+        Wad(self.supply_index).apply_to(shares, rounding)
+    }
+
+    pub fn deposit_amount_to_shares(&self, amount: u64, rounding: Rounding) -> Result<u64> {
+        Wad(self.supply_index).divide(amount, rounding)
+    }
+
+    pub fn borrow_shares_to_amount(&self, shares: u64, rounding: Rounding) -> Result<u64> {
+        Wad(self.borrow_index).apply_to(shares, rounding)
+    }
+
+    pub fn borrow_amount_to_shares(&self, amount: u64, rounding: Rounding) -> Result<u64> {
+        Wad(self.borrow_index).divide(amount, rounding)
+    }
+}
+
+/// Transfers `amount` from `from` into the bank's `vault` and returns what
+/// actually reached it, net of any Token-2022 transfer fee. Callers must
+/// credit the returned amount rather than `amount`.
+pub fn transfer_in_measured<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    vault: &mut InterfaceAccount<'info, TokenAccount>,
+    authority: &Signer<'info>,
+    amount: u64,
+) -> Result<u64> {
+    let vault_balance_before = vault.amount;
+
+    let transfer_accounts = TransferChecked {
+        from: from.to_account_info(),
+        mint: mint.to_account_info(),
+        to: vault.to_account_info(),
+        authority: authority.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(token_program.to_account_info(), transfer_accounts);
+    transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+    vault.reload()?;
+    vault
+        .amount
+        .checked_sub(vault_balance_before)
+        .ok_or(ErrorCode::MathOverflow.into())
+}
+
+/// Amount to send so that `net` reaches the vault once the mint's Token-2022
+/// transfer fee, if any, has been withheld.
+pub fn gross_up_for_transfer_fee(mint: &InterfaceAccount<Mint>, net: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(net);
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let Ok(transfer_fee_config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(net);
+    };
+
+    let fee = transfer_fee_config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, net)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(net.checked_add(fee).ok_or(ErrorCode::MathOverflow)?)
+}
+
+/// Two-slope borrow rate curve. All values are annual rates or
+/// utilizations in basis points.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, InitSpace)]
+pub struct InterestRateModel {
+    pub base_rate: u64,
+    pub slope1: u64,
+    pub optimal_utilization: u64,
+    pub slope2: u64,
+}
+
+impl InterestRateModel {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.optimal_utilization > 0 && self.optimal_utilization <= BPS,
+            ErrorCode::InvalidInterestRateModel
+        );
+
+        // The rate at full utilization is the highest the curve reaches.
+        let max_borrow_rate = self
+            .base_rate
+            .checked_add(self.slope1)
+            .and_then(|rate| rate.checked_add(self.slope2))
+            .ok_or(ErrorCode::InvalidInterestRateModel)?;
+        require!(
+            max_borrow_rate <= MAX_BORROW_RATE_BPS,
+            ErrorCode::InvalidInterestRateModel
+        );
+        Ok(())
+    }
+
+    pub fn borrow_rate(&self, utilization: Wad) -> Result<Wad> {
+        let optimal = Wad::from_bps(self.optimal_utilization);
+        let base_rate = Wad::from_bps(self.base_rate);
+        let slope1 = Wad::from_bps(self.slope1);
+
+        if utilization <= optimal {
+            let slope_rate = slope1
+                .try_mul(utilization, Rounding::Down)?
+                .try_div(optimal, Rounding::Down)?;
+            return base_rate.try_add(slope_rate);
+        }
+
+        let excess = utilization
+            .try_sub(optimal)?
+            .try_div(Wad::ONE.try_sub(optimal)?, Rounding::Down)?;
+        let slope_rate = Wad::from_bps(self.slope2).try_mul(excess, Rounding::Down)?;
+        base_rate.try_add(slope1)?.try_add(slope_rate)
+    }
+}
+
+/// Admin-controlled bank parameters, set at creation and by
+/// `update_bank_config`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, InitSpace)]
+pub struct BankConfig {
+    pub risk_params: RiskParams,
+    pub interest_rate_model: InterestRateModel,
+    /// Portion of borrow interest kept by the protocol, in basis points.
+    pub reserve_factor: u64,
+    /// Maximum total deposits in tokens, or zero for no cap.
+    pub deposit_cap: u64,
+    /// Maximum total borrows in tokens, or zero for no cap.
+    pub borrow_cap: u64,
+    /// Fee charged on flash loans, in basis points.
+    pub flash_loan_fee_bps: u64,
+    pub oracle: OracleConfig,
+}
+
+impl BankConfig {
+    pub fn validate(&self) -> Result<()> {
+        self.risk_params.validate()?;
+        self.interest_rate_model.validate()?;
+        self.oracle.validate()?;
+        require!(self.reserve_factor <= BPS, ErrorCode::InvalidReserveFactor);
+        require!(
+            self.flash_loan_fee_bps <= BPS,
+            ErrorCode::InvalidFlashLoanFee
+        );
+        Ok(())
+    }
+}
+
+/// Collateral and liquidation parameters, all in basis points.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, InitSpace)]
+pub struct RiskParams {
+    /// Share of collateral value that can be borrowed against.
+    pub max_ltv: u64,
+    /// Share of collateral value at which an account becomes liquidatable.
+    pub liquidation_threshold: u64,
+    /// Extra collateral value paid to liquidators on top of the repaid debt.
+    pub liquidation_bonus: u64,
+    /// Maximum share of a borrow that can be repaid in one liquidation.
+    pub liquidation_close_factor: u64,
+}
+
+impl RiskParams {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.max_ltv > 0 && self.max_ltv < self.liquidation_threshold,
+            ErrorCode::InvalidMaxLtv
+        );
+        require!(
+            self.liquidation_threshold <= BPS,
+            ErrorCode::InvalidLiquidationThreshold
+        );
+        // Seizing the bonus must not take more than the collateral backing
+        // the debt at the liquidation threshold.
+        require!(
+            self.liquidation_bonus <= BPS
+                && self.liquidation_threshold * (BPS + self.liquidation_bonus) <= BPS * BPS,
+            ErrorCode::InvalidLiquidationBonus
+        );
+        require!(
+            self.liquidation_close_factor > 0 && self.liquidation_close_factor <= BPS,
+            ErrorCode::InvalidCloseFactor
+        );
+        Ok(())
+    }
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum OracleType {
+    #[default]
+    Pyth,
+}
+
+/// Which Pyth price is used to value the bank's asset.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum PriceMode {
+    #[default]
+    Spot,
+    Ema,
+    /// Lower of spot and EMA for collateral, higher of both for debt.
+    Conservative,
+}
+
+/// Price source used to value the bank's asset.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, InitSpace)]
+pub struct OracleConfig {
+    pub oracle_type: OracleType,
+    pub feed_id: [u8; 32],
+    /// Maximum age of a price update, in seconds.
+    pub max_age: u64,
+    /// Maximum confidence interval relative to the price, in basis points.
+    pub max_confidence_bps: u64,
+    pub price_mode: PriceMode,
+    /// Secondary `PriceFeed` account, or the default key when disabled.
+    pub secondary_oracle: Pubkey,
+    /// Maximum allowed gap between the two sources, in basis points.
+    pub max_deviation_bps: u64,
+}
+
+impl OracleConfig {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.max_age > 0, ErrorCode::InvalidOracleConfig);
+        require!(
+            self.max_confidence_bps > 0 && self.max_confidence_bps < BPS,
+            ErrorCode::InvalidOracleConfig
+        );
+        if self.has_secondary_oracle() {
+            require!(
+                self.max_deviation_bps > 0 && self.max_deviation_bps <= BPS,
+                ErrorCode::InvalidOracleConfig
+            );
+        }
+        Ok(())
+    }
+
+    pub fn has_secondary_oracle(&self) -> bool {
+        self.secondary_oracle != Pubkey::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::SECONDS_PER_YEAR;
+
+    fn risk_params() -> RiskParams {
+        RiskParams {
+            max_ltv: 7_500,
+            liquidation_threshold: 8_000,
+            liquidation_bonus: 500,
+            liquidation_close_factor: 5_000,
+        }
+    }
+
+    fn assert_risk_error(params: RiskParams, expected: ErrorCode) {
+        assert_eq!(params.validate().unwrap_err(), expected.into());
+    }
+
+    #[test]
+    fn risk_params_accept_defaults() {
+        assert!(risk_params().validate().is_ok());
+    }
+
+    #[test]
+    fn risk_params_require_max_ltv_below_threshold() {
+        assert_risk_error(
+            RiskParams {
+                max_ltv: 8_000,
+                ..risk_params()
+            },
+            ErrorCode::InvalidMaxLtv,
+        );
+        assert_risk_error(
+            RiskParams {
+                max_ltv: 0,
+                ..risk_params()
+            },
+            ErrorCode::InvalidMaxLtv,
+        );
+    }
+
+    #[test]
+    fn risk_params_cap_threshold_at_bps() {
+        assert_risk_error(
+            RiskParams {
+                liquidation_threshold: BPS + 1,
+                liquidation_bonus: 0,
+                ..risk_params()
+            },
+            ErrorCode::InvalidLiquidationThreshold,
+        );
+        assert!(RiskParams {
+            liquidation_threshold: BPS,
+            liquidation_bonus: 0,
+            ..risk_params()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn risk_params_bound_bonus_by_threshold() {
+        // 9_500 * (1 + 5.26%) is just under one, 5.27% tips it over.
+        assert!(RiskParams {
+            liquidation_threshold: 9_500,
+            liquidation_bonus: 526,
+            ..risk_params()
+        }
+        .validate()
+        .is_ok());
+        assert_risk_error(
+            RiskParams {
+                liquidation_threshold: 9_500,
+                liquidation_bonus: 527,
+                ..risk_params()
+            },
+            ErrorCode::InvalidLiquidationBonus,
+        );
+    }
+
+    #[test]
+    fn risk_params_bound_close_factor() {
+        assert_risk_error(
+            RiskParams {
+                liquidation_close_factor: 0,
+                ..risk_params()
+            },
+            ErrorCode::InvalidCloseFactor,
+        );
+        assert_risk_error(
+            RiskParams {
+                liquidation_close_factor: BPS + 1,
+                ..risk_params()
+            },
+            ErrorCode::InvalidCloseFactor,
+        );
+        assert!(RiskParams {
+            liquidation_close_factor: BPS,
+            ..risk_params()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    fn interest_rate_model() -> InterestRateModel {
+        InterestRateModel {
+            base_rate: 100,
+            slope1: 400,
+            optimal_utilization: 8_000,
+            slope2: 7_500,
+        }
+    }
+
+    fn bank(total_deposits: u64, total_borrowed: u64) -> Bank {
+        Bank {
+            market: Pubkey::default(),
+            authority: Pubkey::default(),
+            pending_authority: Pubkey::default(),
+            mint_address: Pubkey::default(),
+            mint_decimals: 6,
+            total_deposits,
+            total_deposits_shares: total_deposits,
+            virtual_deposit_shares: 0,
+            virtual_reserve: 0,
+            total_borrowed,
+            total_borrowed_shares: total_borrowed,
+            liquidation_threshold: 8_000,
+            liquidation_bonus: 500,
+            liquidation_close_factor: 5_000,
+            max_ltv: 7_500,
+            last_updated: 0,
+            interest_rate_model: InterestRateModel {
+                base_rate: 0,
+                ..interest_rate_model()
+            },
+            reserve_factor: 1_000,
+            accumulated_protocol_fees: 0,
+            oracle: OracleConfig::default(),
+            deposit_cap: 0,
+            borrow_cap: 0,
+            flash_loan_fee_bps: 0,
+            flash_loan_amount: 0,
+            supply_index: Wad::ONE.0,
+            borrow_index: Wad::ONE.0,
+        }
+    }
+
+    #[test]
+    fn borrow_rate_follows_the_first_slope_up_to_optimal() {
+        let model = interest_rate_model();
+        assert_eq!(model.borrow_rate(Wad::ZERO).unwrap(), Wad::from_bps(100));
+        assert_eq!(
+            model.borrow_rate(Wad::from_bps(4_000)).unwrap(),
+            Wad::from_bps(300)
+        );
+        assert_eq!(
+            model.borrow_rate(Wad::from_bps(8_000)).unwrap(),
+            Wad::from_bps(500)
+        );
+    }
+
+    #[test]
+    fn borrow_rate_follows_the_second_slope_past_optimal() {
+        let model = interest_rate_model();
+        assert_eq!(
+            model.borrow_rate(Wad::from_bps(9_000)).unwrap(),
+            Wad::from_bps(4_250)
+        );
+        assert_eq!(model.borrow_rate(Wad::ONE).unwrap(), Wad::from_bps(8_000));
+    }
+
+    #[test]
+    fn utilization_is_capped_at_one() {
+        assert_eq!(bank(0, 0).utilization().unwrap(), Wad::ZERO);
+        assert_eq!(
+            bank(1_000, 800).utilization().unwrap(),
+            Wad::from_bps(8_000)
+        );
+        assert_eq!(bank(1_000, 1_500).utilization().unwrap(), Wad::ONE);
+    }
+
+    #[test]
+    fn accrual_splits_interest_by_reserve_factor() {
+        let mut bank = bank(1_000_000_000, 800_000_000);
+        bank.accrue_interest(SECONDS_PER_YEAR as i64).unwrap();
+
+        // 4% a year at 80% utilization compounds to 4.0810666% of the
+        // borrows, rounded up.
+        let interest = 32_648_534;
+        assert_eq!(bank.total_borrowed, 800_000_000 + interest);
+
+        // The reserve factor's 10% goes to the protocol, rounded up.
+        assert_eq!(bank.accumulated_protocol_fees, 3_264_854);
+
+        // Depositors earn the rest, so their rate is the borrow rate times
+        // 80% utilization times 90% kept after the reserve factor.
+        assert_eq!(bank.total_deposits, 1_000_000_000 + interest - 3_264_854);
+        let supply_rate = Wad(bank.borrow_index)
+            .try_sub(Wad::ONE)
+            .unwrap()
+            .try_mul(Wad::from_bps(8_000), Rounding::Down)
+            .unwrap()
+            .try_mul(Wad::from_bps(9_000), Rounding::Down)
+            .unwrap();
+        let expected_deposits =
+            1_000_000_000 + supply_rate.apply_to(1_000_000_000, Rounding::Down).unwrap();
+        assert!(bank.total_deposits.abs_diff(expected_deposits) <= 1);
+        assert_eq!(bank.virtual_reserve, 0);
+        assert_eq!(bank.last_updated, SECONDS_PER_YEAR as i64);
+    }
 }
@@ -0,0 +1,8 @@
+use anchor_lang::prelude::*;
+
+/// Groups banks under a single admin who decides which assets are listed.
+#[account]
+#[derive(InitSpace)]
+pub struct LendingMarket {
+    pub admin: Pubkey,
+}
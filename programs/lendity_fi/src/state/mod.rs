@@ -1,5 +1,9 @@
 pub mod bank;
+pub mod lending_market;
+pub mod price_feed;
 pub mod user;
 
 pub use bank::*;
+pub use lending_market::*;
+pub use price_feed::*;
 pub use user::*;
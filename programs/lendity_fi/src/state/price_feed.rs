@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+/// Generic price account read as a bank's secondary oracle source. Any
+/// publisher can write prices in this layout; banks only trust the account
+/// address they were configured with.
+#[account]
+#[derive(InitSpace)]
+pub struct PriceFeed {
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
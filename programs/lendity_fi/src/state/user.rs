@@ -1,21 +1,65 @@
 use anchor_lang::prelude::*;
 
+use crate::{error::ErrorCode, MAX_POSITIONS};
+
 #[account]
 #[derive(InitSpace)]
 pub struct User {
     pub owner: Pubkey,
+    pub market: Pubkey,
+    #[max_len(MAX_POSITIONS)]
+    pub positions: Vec<Position>,
+}
+
+/// Deposit and borrow shares the user holds in a single bank.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, InitSpace)]
+pub struct Position {
+    pub bank: Pubkey,
+    pub mint: Pubkey,
+    /// Receipt tokens locked in the bank's collateral vault.
+    pub deposit_shares: u64,
+    pub borrow_shares: u64,
+}
+
+impl Position {
+    pub fn is_empty(&self) -> bool {
+        self.deposit_shares == 0 && self.borrow_shares == 0
+    }
+}
+
+impl User {
+    pub fn position(&self, bank: &Pubkey) -> Option<&Position> {
+        self.positions
+            .iter()
+            .find(|position| position.bank == *bank)
+    }
+
+    pub fn position_mut(&mut self, bank: &Pubkey) -> Option<&mut Position> {
+        self.positions
+            .iter_mut()
+            .find(|position| position.bank == *bank)
+    }
+
+    pub fn get_or_create_position(&mut self, bank: Pubkey, mint: Pubkey) -> Result<&mut Position> {
+        if let Some(index) = self.positions.iter().position(|p| p.bank == bank) {
+            return Ok(&mut self.positions[index]);
+        }
 
-    pub deposited_sol: u64,
-    pub deposited_sol_shares: u64,
-    pub borrowed_sol: u64,
-    pub borrowed_sol_shares: u64,
+        require!(
+            self.positions.len() < MAX_POSITIONS,
+            ErrorCode::TooManyPositions
+        );
 
-    pub deposited_usdc: u64,
-    pub deposited_usdc_shares: u64,
-    pub borrowed_usdc: u64,
-    pub borrowed_usdc_shares: u64,
+        self.positions.push(Position {
+            bank,
+            mint,
+            ..Default::default()
+        });
+        Ok(self.positions.last_mut().unwrap())
+    }
 
-    pub usdc_address: Pubkey,
-    pub last_updated: i64,
-    pub last_updated_borrowed: i64,
+    /// Frees the slots of positions that no longer hold any shares.
+    pub fn remove_empty_positions(&mut self) {
+        self.positions.retain(|position| !position.is_empty());
+    }
 }
@@ -0,0 +1,97 @@
+import { web3 } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { USDC_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants";
+import {
+  LendingFixture,
+  bankAddress,
+  bankConfig,
+  expectProgramError,
+  fundWallet,
+  initializeBank,
+  oracleConfig,
+  startLendingFixture,
+} from "../bankrun-utils/lendingFixture";
+import { createTestMint } from "../bankrun-utils/tokens";
+
+describe("Bank administration", () => {
+  const newAuthority = web3.Keypair.generate();
+  const stranger = web3.Keypair.generate();
+
+  let fixture: LendingFixture;
+  let bank: web3.PublicKey;
+
+  const proposeAuthority = (signer: web3.Keypair, candidate: web3.PublicKey) =>
+    fixture.program.methods
+      .proposeAuthority(candidate)
+      .accountsPartial({ signer: signer.publicKey, bank })
+      .signers([signer])
+      .rpc();
+
+  const acceptAuthority = (signer: web3.Keypair) =>
+    fixture.program.methods
+      .acceptAuthority()
+      .accountsPartial({ signer: signer.publicKey, bank })
+      .signers([signer])
+      .rpc();
+
+  beforeAll(async () => {
+    fixture = await startLendingFixture();
+    fundWallet(fixture, newAuthority.publicKey);
+    fundWallet(fixture, stranger.publicKey);
+
+    const mint = await createTestMint(fixture.provider, 6);
+    bank = bankAddress(fixture, mint);
+    await initializeBank(fixture, mint, bankConfig(oracleConfig(USDC_USD_PRICE_FEED_ID_HEX)));
+  });
+
+  test("Only lets the market admin list a bank", async () => {
+    const mint = await createTestMint(fixture.provider, 6);
+
+    await expectProgramError(
+      fixture,
+      fixture.program.methods
+        .initializeBank(bankConfig(oracleConfig(USDC_USD_PRICE_FEED_ID_HEX)))
+        .accountsPartial({
+          signer: stranger.publicKey,
+          market: fixture.market,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([stranger])
+        .rpc(),
+      "NotMarketAdmin"
+    );
+    expect(await fixture.context.banksClient.getAccount(bankAddress(fixture, mint))).toBeNull();
+  });
+
+  test("Only lets the bank authority propose a new authority", async () => {
+    await expectProgramError(
+      fixture,
+      proposeAuthority(stranger, stranger.publicKey),
+      "Unauthorized"
+    );
+  });
+
+  test("Only lets the pending authority accept", async () => {
+    await proposeAuthority(fixture.payer, newAuthority.publicKey);
+
+    await expectProgramError(fixture, acceptAuthority(stranger), "NotPendingAuthority");
+  });
+
+  test("Hands over the bank and clears the pending authority", async () => {
+    await acceptAuthority(newAuthority);
+
+    const bankState = await fixture.program.account.bank.fetch(bank);
+    expect(bankState.authority.equals(newAuthority.publicKey)).toBe(true);
+    expect(bankState.pendingAuthority.equals(web3.PublicKey.default)).toBe(true);
+
+    // The cleared slot can't be claimed a second time, and the old authority
+    // has lost its rights.
+    await expectProgramError(fixture, acceptAuthority(newAuthority), "NotPendingAuthority");
+    await expectProgramError(
+      fixture,
+      proposeAuthority(fixture.payer, fixture.payer.publicKey),
+      "Unauthorized"
+    );
+  });
+});
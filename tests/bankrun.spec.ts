@@ -5,9 +5,10 @@ import { BanksClient, ProgramTestContext, startAnchor } from "solana-bankrun";
 import { BankrunContextWrapper } from "../bankrun-utils/bankrunConnection";
 import { LendityFi } from "../target/types/lendity_fi";
 import LendityFiIdl from "../target/idl/lendity_fi.json"
-import { DEVNET_RPC_ENDPOINT, PYTH_PUBLIC_ADDRESS, SOL_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants"
+import { DEVNET_RPC_ENDPOINT, PYTH_PUBLIC_ADDRESS, SOL_USD_PRICE_FEED_ID_HEX, USDC_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants"
 import { createAccount, createMint, mintTo } from "spl-token-bankrun"
 import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { bankConfig, oracleConfig } from "../bankrun-utils/lendingFixture";
 
 describe("Lendity-Fi", () => {
   const web3 = anchor.web3;
@@ -25,6 +26,16 @@ describe("Lendity-Fi", () => {
   let solTokenAccount: anchor.web3.PublicKey;
   let solUsdFeedAccountAddress: string;
 
+  const market = web3.Keypair.generate();
+
+  // The devnet feeds are priced conservatively and allowed to be a little
+  // older than the fixture default.
+  const conservativeOracle = (feedIdHex: string) => ({
+    ...oracleConfig(feedIdHex),
+    maxAge: new anchor.BN(100),
+    priceMode: { conservative: {} },
+  });
+
   beforeAll(async () => {
     const pythAccountInfo = await devnetConnection.getAccountInfo(pyth);
     context = await startAnchor(
@@ -68,21 +79,34 @@ describe("Lendity-Fi", () => {
 
 
     [usdcTokenAccount] = web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("bank_token_account"), usdcMint.toBuffer()],
+      [Buffer.from("bank_token_account"), market.publicKey.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
     [solTokenAccount] = web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("bank_token_account"), solMint.toBuffer()],
+      [Buffer.from("bank_token_account"), market.publicKey.toBuffer(), solMint.toBuffer()],
       program.programId
     );
 
   });
 
+  test("Initializes the lending market", async () => {
+    const initMarketTx = await program.methods
+      .initializeMarket()
+      .accounts({
+        signer: signer.publicKey,
+        market: market.publicKey,
+      })
+      .signers([market])
+      .rpc();
+
+    console.log({ initMarketTx });
+  });
+
   test("Initializes the user account", async() => {
     const initUserTx = await program.methods
-      .initializeUser(usdcMint)
-      .accounts({signer: signer.publicKey})
+      .initializeUser()
+      .accounts({signer: signer.publicKey, market: market.publicKey})
       .rpc();
 
     console.log({initUserTx});
@@ -91,9 +115,10 @@ describe("Lendity-Fi", () => {
 
   test("Initializes the USDC bank and funds it's token account", async () => {
     const initUsdcBankTx = await program.methods
-      .initializeBank(new anchor.BN(1), new anchor.BN(1))
+      .initializeBank(bankConfig(conservativeOracle(USDC_USD_PRICE_FEED_ID_HEX)))
       .accounts({
         signer: signer.publicKey,
+        market: market.publicKey,
         mint: usdcMint,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
@@ -116,9 +141,10 @@ describe("Lendity-Fi", () => {
 
   test("Initializes the SOL bank and funds it's token account", async () => {
     const initSolBankTx = await program.methods
-      .initializeBank(new anchor.BN(1), new anchor.BN(1))
+      .initializeBank(bankConfig(conservativeOracle(SOL_USD_PRICE_FEED_ID_HEX)))
       .accounts({
         signer: signer.publicKey,
+        market: market.publicKey,
         mint: solMint,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
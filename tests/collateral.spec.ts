@@ -0,0 +1,154 @@
+import { BN, web3 } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import { SOL_USD_PRICE_FEED_ID_HEX, USDC_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants";
+import {
+  LendingFixture,
+  bankAddress,
+  bankConfig,
+  currentTimestamp,
+  deposit,
+  depositCollateral,
+  expectProgramError,
+  fundWallet,
+  healthAccounts,
+  initializeBank,
+  initializeUser,
+  oracleConfig,
+  receiptMintAddress,
+  startLendingFixture,
+} from "../bankrun-utils/lendingFixture";
+import { setMockPriceUpdate } from "../bankrun-utils/mockPriceFeed";
+import { createTestMint, createTokenAccount, mintTokens, tokenBalance } from "../bankrun-utils/tokens";
+
+describe("Collateral withdrawal", () => {
+  const collateralPriceUpdate = web3.Keypair.generate().publicKey;
+  const borrowedPriceUpdate = web3.Keypair.generate().publicKey;
+  const borrower = web3.Keypair.generate();
+
+  let fixture: LendingFixture;
+  let collateralMint: web3.PublicKey;
+  let borrowedMint: web3.PublicKey;
+  let collateralBank: web3.PublicKey;
+  let borrowedBank: web3.PublicKey;
+
+  const setPrice = async (address: web3.PublicKey, feedIdHex: string) =>
+    setMockPriceUpdate(fixture.context, address, feedIdHex, {
+      price: new BN(100_000_000),
+      conf: new BN(0),
+      exponent: -8,
+      publishTime: await currentTimestamp(fixture),
+    });
+
+  const withdrawCollateral = (shares: number, remainingAccounts: web3.AccountMeta[]) =>
+    fixture.program.methods
+      .withdrawCollateral(new BN(shares))
+      .accountsPartial({
+        signer: borrower.publicKey,
+        market: fixture.market,
+        mint: collateralMint,
+        userReceiptTokenAccount: getAssociatedTokenAddressSync(
+          receiptMintAddress(fixture, collateralMint),
+          borrower.publicKey
+        ),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers([borrower])
+      .rpc();
+
+  const collateralAccounts = () => healthAccounts(collateralBank, collateralPriceUpdate);
+  const borrowedAccounts = () => healthAccounts(borrowedBank, borrowedPriceUpdate);
+
+  beforeAll(async () => {
+    fixture = await startLendingFixture();
+    fundWallet(fixture, borrower.publicKey);
+
+    collateralMint = await createTestMint(fixture.provider, 6);
+    borrowedMint = await createTestMint(fixture.provider, 6);
+    collateralBank = bankAddress(fixture, collateralMint);
+    borrowedBank = bankAddress(fixture, borrowedMint);
+    await initializeBank(
+      fixture,
+      collateralMint,
+      bankConfig(oracleConfig(SOL_USD_PRICE_FEED_ID_HEX))
+    );
+    await initializeBank(
+      fixture,
+      borrowedMint,
+      bankConfig(oracleConfig(USDC_USD_PRICE_FEED_ID_HEX))
+    );
+    await setPrice(collateralPriceUpdate, SOL_USD_PRICE_FEED_ID_HEX);
+    await setPrice(borrowedPriceUpdate, USDC_USD_PRICE_FEED_ID_HEX);
+
+    const supplierAccount = await createTokenAccount(
+      fixture.provider,
+      borrowedMint,
+      fixture.payer.publicKey
+    );
+    await mintTokens(fixture.provider, borrowedMint, supplierAccount, new BN(10_000_000_000));
+    await deposit(fixture, fixture.payer, borrowedMint, new BN(10_000_000_000));
+
+    // 1000 collateral tokens at $1 back a 700 borrow, so about 66 tokens can
+    // be unlocked before the 75% max LTV is reached.
+    await initializeUser(fixture, borrower);
+    const borrowerCollateralAccount = await createTokenAccount(
+      fixture.provider,
+      collateralMint,
+      borrower.publicKey
+    );
+    await mintTokens(fixture.provider, collateralMint, borrowerCollateralAccount, new BN(1_000_000_000));
+    await depositCollateral(fixture, borrower, collateralMint, new BN(1_000_000_000));
+
+    await fixture.program.methods
+      .borrow(new BN(700_000_000))
+      .accountsPartial({
+        signer: borrower.publicKey,
+        market: fixture.market,
+        mint: borrowedMint,
+        priceUpdate: borrowedPriceUpdate,
+        secondaryPriceUpdate: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(collateralAccounts())
+      .signers([borrower])
+      .rpc();
+  });
+
+  test("Refuses to unlock collateral past the max LTV", async () => {
+    // 900 left at 75% only covers 675 of the 700 debt.
+    await expectProgramError(
+      fixture,
+      withdrawCollateral(100_000_000, [...collateralAccounts(), ...borrowedAccounts()]),
+      "WithdrawalLeavesUnhealthyAccount"
+    );
+  });
+
+  test("Rejects health accounts missing a position", async () => {
+    await expectProgramError(
+      fixture,
+      withdrawCollateral(10_000_000, collateralAccounts()),
+      "InvalidHealthAccounts"
+    );
+  });
+
+  test("Rejects health accounts out of position order", async () => {
+    await expectProgramError(
+      fixture,
+      withdrawCollateral(10_000_001, [...borrowedAccounts(), ...collateralAccounts()]),
+      "InvalidHealthAccounts"
+    );
+  });
+
+  test("Unlocks collateral that keeps the account within the max LTV", async () => {
+    const receiptAccount = getAssociatedTokenAddressSync(
+      receiptMintAddress(fixture, collateralMint),
+      borrower.publicKey
+    );
+    const receiptsBefore = await tokenBalance(fixture.context, receiptAccount);
+
+    await withdrawCollateral(10_000_000, [...collateralAccounts(), ...borrowedAccounts()]);
+
+    const receiptsAfter = await tokenBalance(fixture.context, receiptAccount);
+    expect(receiptsAfter.sub(receiptsBefore).toNumber()).toBe(10_000_000);
+  });
+});
@@ -0,0 +1,182 @@
+import { BN, web3 } from "@coral-xyz/anchor";
+import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
+import { USDC_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants";
+import {
+  LendingFixture,
+  bankAddress,
+  bankConfig,
+  bankTokenAccountAddress,
+  currentTimestamp,
+  deposit,
+  depositCollateral,
+  expectProgramError,
+  healthAccounts,
+  initializeBank,
+  initializeUser,
+  oracleConfig,
+  startLendingFixture,
+  userAddress,
+} from "../bankrun-utils/lendingFixture";
+import { setMockPriceUpdate } from "../bankrun-utils/mockPriceFeed";
+import { createTestMint, createTokenAccount, mintTokens, tokenBalance } from "../bankrun-utils/tokens";
+
+describe("Token-2022 transfer fee mints", () => {
+  const transferFeeBps = 100;
+  const priceUpdate = web3.Keypair.generate().publicKey;
+
+  let fixture: LendingFixture;
+  let user: web3.Keypair;
+  let mint: web3.PublicKey;
+  let bank: web3.PublicKey;
+  let userTokenAccount: web3.PublicKey;
+
+  // Amount the token program withholds from a transfer of `amount`.
+  const transferFee = (amount: BN) => amount.muln(transferFeeBps).addn(9_999).divn(10_000);
+
+  // Smallest transfer that still delivers `net` after the fee.
+  const grossUp = (net: BN) =>
+    net
+      .muln(10_000)
+      .addn(10_000 - transferFeeBps - 1)
+      .divn(10_000 - transferFeeBps);
+
+  const borrowShares = async () => {
+    const userAccount = await fixture.program.account.user.fetch(
+      userAddress(fixture, user.publicKey)
+    );
+    const position = userAccount.positions.find((position) => position.bank.equals(bank));
+    return position?.borrowShares ?? new BN(0);
+  };
+
+  beforeAll(async () => {
+    fixture = await startLendingFixture();
+    user = fixture.payer;
+
+    mint = await createTestMint(fixture.provider, 6, TOKEN_2022_PROGRAM_ID, {
+      basisPoints: transferFeeBps,
+      maximumFee: BigInt(1_000_000_000_000),
+    });
+    bank = bankAddress(fixture, mint);
+    await initializeBank(
+      fixture,
+      mint,
+      bankConfig(oracleConfig(USDC_USD_PRICE_FEED_ID_HEX)),
+      TOKEN_2022_PROGRAM_ID
+    );
+    setMockPriceUpdate(fixture.context, priceUpdate, USDC_USD_PRICE_FEED_ID_HEX, {
+      price: new BN(100_000_000),
+      conf: new BN(0),
+      exponent: -8,
+      publishTime: await currentTimestamp(fixture),
+    });
+
+    await initializeUser(fixture, user);
+    userTokenAccount = await createTokenAccount(
+      fixture.provider,
+      mint,
+      user.publicKey,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTokens(
+      fixture.provider,
+      mint,
+      userTokenAccount,
+      new BN(10_000_000_000),
+      TOKEN_2022_PROGRAM_ID
+    );
+  });
+
+  test("Credits a deposit with only what reached the vault", async () => {
+    const amount = new BN(1_000_000_000);
+    const received = amount.sub(transferFee(amount));
+
+    const bankBefore = await fixture.program.account.bank.fetch(bank);
+    const receipts = await deposit(fixture, user, mint, amount, TOKEN_2022_PROGRAM_ID);
+    const bankAfter = await fixture.program.account.bank.fetch(bank);
+
+    expect(receipts.toString()).toBe(received.toString());
+    expect(bankAfter.totalDeposits.sub(bankBefore.totalDeposits).toString()).toBe(
+      received.toString()
+    );
+  });
+
+  test("Lets a borrower repay the whole debt plus the transfer fee", async () => {
+    const borrowAmount = new BN(100_000_000);
+    await depositCollateral(fixture, user, mint, new BN(1_000_000_000), TOKEN_2022_PROGRAM_ID);
+
+    await fixture.program.methods
+      .borrow(borrowAmount)
+      .accountsPartial({
+        signer: user.publicKey,
+        market: fixture.market,
+        mint,
+        priceUpdate,
+        secondaryPriceUpdate: null,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .remainingAccounts(healthAccounts(bank, priceUpdate))
+      .rpc();
+
+    const repay = (amount: BN) =>
+      fixture.program.methods
+        .repay(amount)
+        .accountsPartial({
+          signer: user.publicKey,
+          market: fixture.market,
+          mint,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+
+    // No time has passed, so the debt is still the borrowed amount.
+    const grossRepay = grossUp(borrowAmount);
+    await expectProgramError(fixture, repay(grossRepay.addn(1)), "OverRepayAmount");
+
+    const bankBefore = await fixture.program.account.bank.fetch(bank);
+    await repay(grossRepay);
+    const bankAfter = await fixture.program.account.bank.fetch(bank);
+
+    expect((await borrowShares()).toNumber()).toBe(0);
+    expect(bankBefore.totalBorrowed.sub(bankAfter.totalBorrowed).toString()).toBe(
+      borrowAmount.toString()
+    );
+  });
+
+  test("Repays a flash loan in full despite the transfer fee", async () => {
+    const amount = new BN(50_000_000);
+    const flashLoanFee = amount.muln(9).addn(9_999).divn(10_000);
+    const vault = bankTokenAccountAddress(fixture, mint);
+    const vaultBefore = await tokenBalance(fixture.context, vault);
+
+    const flashBorrowIx = await fixture.program.methods
+      .flashBorrow(amount)
+      .accountsPartial({
+        signer: user.publicKey,
+        market: fixture.market,
+        mint,
+        instructions: web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .instruction();
+    const flashRepayIx = await fixture.program.methods
+      .flashRepay()
+      .accountsPartial({
+        signer: user.publicKey,
+        market: fixture.market,
+        mint,
+        userTokenAccount,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .instruction();
+
+    await fixture.provider.sendAndConfirm(
+      new web3.Transaction().add(flashBorrowIx, flashRepayIx)
+    );
+
+    const bankAfter = await fixture.program.account.bank.fetch(bank);
+    const vaultAfter = await tokenBalance(fixture.context, vault);
+
+    expect(bankAfter.flashLoanAmount.toNumber()).toBe(0);
+    expect(vaultAfter.sub(vaultBefore).gte(flashLoanFee)).toBe(true);
+  });
+});
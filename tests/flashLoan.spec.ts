@@ -0,0 +1,121 @@
+import { BN, web3 } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { USDC_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants";
+import {
+  LendingFixture,
+  bankAddress,
+  bankConfig,
+  bankTokenAccountAddress,
+  deposit,
+  expectProgramError,
+  initializeBank,
+  initializeUser,
+  oracleConfig,
+  startLendingFixture,
+} from "../bankrun-utils/lendingFixture";
+import { createTestMint, createTokenAccount, mintTokens, tokenBalance } from "../bankrun-utils/tokens";
+
+describe("Flash loans", () => {
+  const depositAmount = new BN(1_000_000_000);
+  const flashLoanFeeBps = 9;
+
+  let fixture: LendingFixture;
+  let user: web3.Keypair;
+  let mint: web3.PublicKey;
+  let bank: web3.PublicKey;
+  let userTokenAccount: web3.PublicKey;
+
+  const flashBorrowIx = (amount: number) =>
+    fixture.program.methods
+      .flashBorrow(new BN(amount))
+      .accountsPartial({
+        signer: user.publicKey,
+        market: fixture.market,
+        mint,
+        instructions: web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .instruction();
+
+  const flashRepayIx = () =>
+    fixture.program.methods
+      .flashRepay()
+      .accountsPartial({
+        signer: user.publicKey,
+        market: fixture.market,
+        mint,
+        userTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .instruction();
+
+  const send = async (...instructions: web3.TransactionInstruction[]) =>
+    fixture.provider.sendAndConfirm(new web3.Transaction().add(...instructions));
+
+  beforeAll(async () => {
+    fixture = await startLendingFixture();
+    user = fixture.payer;
+
+    mint = await createTestMint(fixture.provider, 6);
+    bank = bankAddress(fixture, mint);
+    await initializeBank(fixture, mint, bankConfig(oracleConfig(USDC_USD_PRICE_FEED_ID_HEX)));
+
+    // Half is supplied as liquidity, the rest pays flash loan fees.
+    await initializeUser(fixture, user);
+    userTokenAccount = await createTokenAccount(fixture.provider, mint, user.publicKey);
+    await mintTokens(fixture.provider, mint, userTokenAccount, depositAmount.muln(2));
+    await deposit(fixture, user, mint, depositAmount);
+  });
+
+  test("Rejects a flash borrow without a later flash repay", async () => {
+    await expectProgramError(fixture, send(await flashBorrowIx(1_000_000)), "MissingFlashRepay");
+  });
+
+  test("Rejects a flash repay with the bank at the wrong account index", async () => {
+    const repayIx = await flashRepayIx();
+    const bankIndex = repayIx.keys.findIndex((key) => key.pubkey.equals(bank));
+    expect(bankIndex).toBe(3);
+    [repayIx.keys[3], repayIx.keys[4]] = [repayIx.keys[4], repayIx.keys[3]];
+
+    await expectProgramError(
+      fixture,
+      send(await flashBorrowIx(1_000_001), repayIx),
+      "MissingFlashRepay"
+    );
+  });
+
+  test("Rejects a second flash borrow while one is open", async () => {
+    await expectProgramError(
+      fixture,
+      send(await flashBorrowIx(1_000_002), await flashBorrowIx(1_000_003), await flashRepayIx()),
+      "FlashLoanActive"
+    );
+  });
+
+  test("Pays the flash loan fee to depositors", async () => {
+    const amount = 500_000_000;
+    const fee = new BN(amount).muln(flashLoanFeeBps).addn(9_999).divn(10_000);
+
+    const vault = bankTokenAccountAddress(fixture, mint);
+    const bankBefore = await fixture.program.account.bank.fetch(bank);
+    const vaultBefore = await tokenBalance(fixture.context, vault);
+
+    await send(await flashBorrowIx(amount), await flashRepayIx());
+
+    const bankAfter = await fixture.program.account.bank.fetch(bank);
+    const vaultAfter = await tokenBalance(fixture.context, vault);
+
+    expect(vaultAfter.sub(vaultBefore).toString()).toBe(fee.toString());
+    expect(bankAfter.flashLoanAmount.toNumber()).toBe(0);
+    expect(bankAfter.supplyIndex.gt(bankBefore.supplyIndex)).toBe(true);
+
+    // The part of the fee owed to the bank's virtual shares stays unclaimed.
+    const shares = bankBefore.totalDepositsShares;
+    const expectedIncrease = fee
+      .mul(shares)
+      .div(shares.add(bankBefore.virtualDepositShares));
+    expect(bankAfter.totalDeposits.sub(bankBefore.totalDeposits).toString()).toBe(
+      expectedIncrease.toString()
+    );
+  });
+});
@@ -0,0 +1,85 @@
+import { BN, web3 } from "@coral-xyz/anchor";
+import { USDC_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants";
+import {
+  LendingFixture,
+  bankAddress,
+  bankConfig,
+  bankTokenAccountAddress,
+  deposit,
+  fundWallet,
+  initializeBank,
+  initializeUser,
+  oracleConfig,
+  startLendingFixture,
+} from "../bankrun-utils/lendingFixture";
+import { createTestMint, createTokenAccount, mintTokens, transferTokens } from "../bankrun-utils/tokens";
+
+describe("First depositor inflation", () => {
+  const donation = new BN(1_000_000_000);
+  const wad = new BN(10).pow(new BN(18));
+
+  let fixture: LendingFixture;
+  let attacker: web3.Keypair;
+  let victim: web3.Keypair;
+  let mint: web3.PublicKey;
+  let bank: web3.PublicKey;
+
+  beforeAll(async () => {
+    fixture = await startLendingFixture();
+    attacker = fixture.payer;
+    victim = web3.Keypair.generate();
+    fundWallet(fixture, victim.publicKey);
+
+    mint = await createTestMint(fixture.provider, 6);
+    bank = bankAddress(fixture, mint);
+    await initializeBank(fixture, mint, {
+      ...bankConfig(oracleConfig(USDC_USD_PRICE_FEED_ID_HEX)),
+      reserveFactor: new BN(0),
+    });
+
+    await initializeUser(fixture, attacker);
+    const attackerTokenAccount = await createTokenAccount(
+      fixture.provider,
+      mint,
+      attacker.publicKey
+    );
+    await mintTokens(fixture.provider, mint, attackerTokenAccount, donation.addn(1));
+
+    await initializeUser(fixture, victim);
+    const victimTokenAccount = await createTokenAccount(fixture.provider, mint, victim.publicKey);
+    await mintTokens(fixture.provider, mint, victimTokenAccount, new BN(1_000_000));
+
+    // The attacker takes the first share with a single unit, then donates to
+    // the vault and folds the donation into the supply index.
+    await deposit(fixture, attacker, mint, new BN(1));
+    await transferTokens(
+      fixture.provider,
+      attackerTokenAccount,
+      bankTokenAccountAddress(fixture, mint),
+      donation
+    );
+    await fixture.program.methods
+      .syncBank()
+      .accountsPartial({ market: fixture.market, mint })
+      .rpc();
+  });
+
+  test("Mints the victim receipts worth their deposit", async () => {
+    const amount = new BN(1_000_000);
+    const receipts = await deposit(fixture, victim, mint, amount);
+    const bankState = await fixture.program.account.bank.fetch(bank);
+    const value = receipts.mul(bankState.supplyIndex).div(wad);
+
+    // Without virtual shares the index would exceed the whole deposit and
+    // the victim would get nothing.
+    expect(receipts.gtn(0)).toBe(true);
+    expect(value.muln(1_000).gte(amount.muln(998))).toBe(true);
+  });
+
+  test("Leaves the attacker with only a sliver of the donation", async () => {
+    const bankState = await fixture.program.account.bank.fetch(bank);
+    const attackerValue = bankState.supplyIndex.div(wad);
+
+    expect(attackerValue.lte(donation.divn(1_000).addn(1))).toBe(true);
+  });
+});
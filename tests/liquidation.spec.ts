@@ -0,0 +1,369 @@
+import { BN, web3 } from "@coral-xyz/anchor";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
+import { SOL_USD_PRICE_FEED_ID_HEX, USDC_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants";
+import {
+  LendingFixture,
+  bankAddress,
+  bankConfig,
+  currentTimestamp,
+  deposit,
+  depositCollateral,
+  expectProgramError,
+  fundWallet,
+  healthAccounts,
+  initializeBank,
+  initializeUser,
+  oracleConfig,
+  startLendingFixture,
+  userAddress,
+} from "../bankrun-utils/lendingFixture";
+import { setMockPriceUpdate } from "../bankrun-utils/mockPriceFeed";
+import { createTestMint, createTokenAccount, mintTokens, tokenBalance } from "../bankrun-utils/tokens";
+
+describe("Liquidation", () => {
+  const collateralPriceUpdate = web3.Keypair.generate().publicKey;
+  const borrowedPriceUpdate = web3.Keypair.generate().publicKey;
+  const borrower = web3.Keypair.generate();
+
+  let fixture: LendingFixture;
+  let liquidator: web3.Keypair;
+  let collateralMint: web3.PublicKey;
+  let borrowedMint: web3.PublicKey;
+  let collateralBank: web3.PublicKey;
+  let borrowedBank: web3.PublicKey;
+
+  // Sets a fresh USD price with eight decimals.
+  const setPrice = async (address: web3.PublicKey, feedIdHex: string, price: number) =>
+    setMockPriceUpdate(fixture.context, address, feedIdHex, {
+      price: new BN(price),
+      conf: new BN(0),
+      exponent: -8,
+      publishTime: await currentTimestamp(fixture),
+    });
+
+  const liquidate = (repayAmount: BN, minCollateralOut: BN) =>
+    fixture.program.methods
+      .liquidate(repayAmount, minCollateralOut)
+      .accountsPartial({
+        liquidator: liquidator.publicKey,
+        market: fixture.market,
+        borrower: borrower.publicKey,
+        collateralMint,
+        borrowedMint,
+        collateralPriceUpdate,
+        collateralSecondaryPriceUpdate: null,
+        borrowedPriceUpdate,
+        borrowedSecondaryPriceUpdate: null,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+        borrowedTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        ...healthAccounts(collateralBank, collateralPriceUpdate),
+        ...healthAccounts(borrowedBank, borrowedPriceUpdate),
+      ])
+      .rpc();
+
+  const fetchPositions = async () => {
+    const user = await fixture.program.account.user.fetch(
+      userAddress(fixture, borrower.publicKey)
+    );
+    const find = (bank: web3.PublicKey) =>
+      user.positions.find((position) => position.bank.equals(bank));
+    return {
+      collateralShares: find(collateralBank)?.depositShares ?? new BN(0),
+      borrowShares: find(borrowedBank)?.borrowShares ?? new BN(0),
+    };
+  };
+
+  beforeAll(async () => {
+    fixture = await startLendingFixture();
+    liquidator = fixture.payer;
+    fundWallet(fixture, borrower.publicKey);
+
+    collateralMint = await createTestMint(fixture.provider, 6);
+    borrowedMint = await createTestMint(fixture.provider, 6);
+    collateralBank = bankAddress(fixture, collateralMint);
+    borrowedBank = bankAddress(fixture, borrowedMint);
+    await initializeBank(
+      fixture,
+      collateralMint,
+      bankConfig(oracleConfig(SOL_USD_PRICE_FEED_ID_HEX))
+    );
+    await initializeBank(
+      fixture,
+      borrowedMint,
+      bankConfig(oracleConfig(USDC_USD_PRICE_FEED_ID_HEX))
+    );
+    await setPrice(collateralPriceUpdate, SOL_USD_PRICE_FEED_ID_HEX, 100_000_000);
+    await setPrice(borrowedPriceUpdate, USDC_USD_PRICE_FEED_ID_HEX, 100_000_000);
+
+    // The liquidator supplies the borrowed asset and keeps the rest to repay
+    // debt with.
+    const liquidatorBorrowedAccount = await createTokenAccount(
+      fixture.provider,
+      borrowedMint,
+      liquidator.publicKey
+    );
+    await mintTokens(fixture.provider, borrowedMint, liquidatorBorrowedAccount, new BN(20_000_000_000));
+    await deposit(fixture, liquidator, borrowedMint, new BN(10_000_000_000));
+
+    // The borrower locks 1000 collateral tokens and borrows 700 at $1 each,
+    // below the 75% max LTV.
+    await initializeUser(fixture, borrower);
+    const borrowerCollateralAccount = await createTokenAccount(
+      fixture.provider,
+      collateralMint,
+      borrower.publicKey
+    );
+    await mintTokens(fixture.provider, collateralMint, borrowerCollateralAccount, new BN(1_000_000_000));
+    await depositCollateral(fixture, borrower, collateralMint, new BN(1_000_000_000));
+
+    await fixture.program.methods
+      .borrow(new BN(700_000_000))
+      .accountsPartial({
+        signer: borrower.publicKey,
+        market: fixture.market,
+        mint: borrowedMint,
+        priceUpdate: borrowedPriceUpdate,
+        secondaryPriceUpdate: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(healthAccounts(collateralBank, collateralPriceUpdate))
+      .signers([borrower])
+      .rpc();
+  });
+
+  test("Refuses to liquidate a healthy account", async () => {
+    await expectProgramError(
+      fixture,
+      liquidate(new BN(100_000_000), new BN(0)),
+      "DoesNotFallBelowHealthFactor"
+    );
+  });
+
+  test("Burns the repaid debt and seized collateral from the borrower and both banks", async () => {
+    // At $0.85 the collateral's liquidation limit is 680, below the 700 debt.
+    await setPrice(collateralPriceUpdate, SOL_USD_PRICE_FEED_ID_HEX, 85_000_000);
+
+    const repaid = new BN(100_000_000);
+    // 100 repaid plus the 5% bonus, paid in collateral at $0.85.
+    const seized = new BN(123_529_411);
+
+    const positionsBefore = await fetchPositions();
+    const collateralBankBefore = await fixture.program.account.bank.fetch(collateralBank);
+    const borrowedBankBefore = await fixture.program.account.bank.fetch(borrowedBank);
+
+    await liquidate(repaid, new BN(0));
+
+    const positionsAfter = await fetchPositions();
+    const collateralBankAfter = await fixture.program.account.bank.fetch(collateralBank);
+    const borrowedBankAfter = await fixture.program.account.bank.fetch(borrowedBank);
+
+    // No time has passed, so both indices are still one and shares equal
+    // token amounts.
+    expect(positionsBefore.borrowShares.sub(positionsAfter.borrowShares).toString()).toBe(
+      repaid.toString()
+    );
+    expect(positionsBefore.collateralShares.sub(positionsAfter.collateralShares).toString()).toBe(
+      seized.toString()
+    );
+
+    expect(borrowedBankBefore.totalBorrowed.sub(borrowedBankAfter.totalBorrowed).toString()).toBe(
+      repaid.toString()
+    );
+    expect(
+      borrowedBankBefore.totalBorrowedShares.sub(borrowedBankAfter.totalBorrowedShares).toString()
+    ).toBe(repaid.toString());
+    expect(
+      collateralBankBefore.totalDeposits.sub(collateralBankAfter.totalDeposits).toString()
+    ).toBe(seized.toString());
+    expect(
+      collateralBankBefore.totalDepositsShares
+        .sub(collateralBankAfter.totalDepositsShares)
+        .toString()
+    ).toBe(seized.toString());
+
+    const liquidatorCollateral = await tokenBalance(
+      fixture.context,
+      getAssociatedTokenAddressSync(collateralMint, liquidator.publicKey)
+    );
+    expect(liquidatorCollateral.toString()).toBe(seized.toString());
+  });
+
+  test("Reverts when the seized collateral is below the liquidator's minimum", async () => {
+    // 10 repaid plus the 5% bonus at $0.85 seizes 12.352941 collateral.
+    await expectProgramError(
+      fixture,
+      liquidate(new BN(10_000_000), new BN(12_352_942)),
+      "LiquidationSlippageExceeded"
+    );
+  });
+
+  test("Caps the repayment at the close factor", async () => {
+    const borrowedBankState = await fixture.program.account.bank.fetch(borrowedBank);
+    const { borrowShares } = await fetchPositions();
+    const wad = new BN(10).pow(new BN(18));
+    const owed = borrowShares.mul(borrowedBankState.borrowIndex).add(wad.subn(1)).div(wad);
+    const maxRepay = owed.muln(5_000).divn(10_000);
+
+    const liquidatorBorrowedAccount = getAssociatedTokenAddressSync(
+      borrowedMint,
+      liquidator.publicKey
+    );
+    const balanceBefore = await tokenBalance(fixture.context, liquidatorBorrowedAccount);
+
+    await liquidate(owed, new BN(0));
+
+    const balanceAfter = await tokenBalance(fixture.context, liquidatorBorrowedAccount);
+    expect(balanceBefore.sub(balanceAfter).toString()).toBe(maxRepay.toString());
+  });
+});
+
+describe("Liquidation of Token-2022 collateral", () => {
+  const collateralPriceUpdate = web3.Keypair.generate().publicKey;
+  const borrowedPriceUpdate = web3.Keypair.generate().publicKey;
+  const borrower = web3.Keypair.generate();
+  const transferFeeBps = 100;
+
+  let fixture: LendingFixture;
+  let liquidator: web3.Keypair;
+  let collateralMint: web3.PublicKey;
+  let borrowedMint: web3.PublicKey;
+  let collateralBank: web3.PublicKey;
+  let borrowedBank: web3.PublicKey;
+
+  const setPrice = async (address: web3.PublicKey, feedIdHex: string, price: number) =>
+    setMockPriceUpdate(fixture.context, address, feedIdHex, {
+      price: new BN(price),
+      conf: new BN(0),
+      exponent: -8,
+      publishTime: await currentTimestamp(fixture),
+    });
+
+  const liquidate = (repayAmount: BN, minCollateralOut: BN) =>
+    fixture.program.methods
+      .liquidate(repayAmount, minCollateralOut)
+      .accountsPartial({
+        liquidator: liquidator.publicKey,
+        market: fixture.market,
+        borrower: borrower.publicKey,
+        collateralMint,
+        borrowedMint,
+        collateralPriceUpdate,
+        collateralSecondaryPriceUpdate: null,
+        borrowedPriceUpdate,
+        borrowedSecondaryPriceUpdate: null,
+        collateralTokenProgram: TOKEN_2022_PROGRAM_ID,
+        borrowedTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        ...healthAccounts(collateralBank, collateralPriceUpdate),
+        ...healthAccounts(borrowedBank, borrowedPriceUpdate),
+      ])
+      .rpc();
+
+  beforeAll(async () => {
+    fixture = await startLendingFixture();
+    liquidator = fixture.payer;
+    fundWallet(fixture, borrower.publicKey);
+
+    collateralMint = await createTestMint(fixture.provider, 6, TOKEN_2022_PROGRAM_ID, {
+      basisPoints: transferFeeBps,
+      maximumFee: BigInt(1_000_000_000_000),
+    });
+    borrowedMint = await createTestMint(fixture.provider, 6);
+    collateralBank = bankAddress(fixture, collateralMint);
+    borrowedBank = bankAddress(fixture, borrowedMint);
+    await initializeBank(
+      fixture,
+      collateralMint,
+      bankConfig(oracleConfig(SOL_USD_PRICE_FEED_ID_HEX)),
+      TOKEN_2022_PROGRAM_ID
+    );
+    await initializeBank(
+      fixture,
+      borrowedMint,
+      bankConfig(oracleConfig(USDC_USD_PRICE_FEED_ID_HEX))
+    );
+    await setPrice(collateralPriceUpdate, SOL_USD_PRICE_FEED_ID_HEX, 100_000_000);
+    await setPrice(borrowedPriceUpdate, USDC_USD_PRICE_FEED_ID_HEX, 100_000_000);
+
+    const liquidatorBorrowedAccount = await createTokenAccount(
+      fixture.provider,
+      borrowedMint,
+      liquidator.publicKey
+    );
+    await mintTokens(fixture.provider, borrowedMint, liquidatorBorrowedAccount, new BN(20_000_000_000));
+    await deposit(fixture, liquidator, borrowedMint, new BN(10_000_000_000));
+
+    // 990 collateral tokens reach the vault after the 1% fee, so 700 at $1
+    // stays below the 75% max LTV.
+    await initializeUser(fixture, borrower);
+    const borrowerCollateralAccount = await createTokenAccount(
+      fixture.provider,
+      collateralMint,
+      borrower.publicKey,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTokens(
+      fixture.provider,
+      collateralMint,
+      borrowerCollateralAccount,
+      new BN(1_000_000_000),
+      TOKEN_2022_PROGRAM_ID
+    );
+    await depositCollateral(
+      fixture,
+      borrower,
+      collateralMint,
+      new BN(1_000_000_000),
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    await fixture.program.methods
+      .borrow(new BN(700_000_000))
+      .accountsPartial({
+        signer: borrower.publicKey,
+        market: fixture.market,
+        mint: borrowedMint,
+        priceUpdate: borrowedPriceUpdate,
+        secondaryPriceUpdate: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(healthAccounts(collateralBank, collateralPriceUpdate))
+      .signers([borrower])
+      .rpc();
+
+    // At $0.85 the collateral's liquidation limit is 673.2, below the debt.
+    await setPrice(collateralPriceUpdate, SOL_USD_PRICE_FEED_ID_HEX, 85_000_000);
+  });
+
+  test("Checks the slippage bound against the collateral received after the fee", async () => {
+    // 10 repaid plus the 5% bonus at $0.85 seizes 12.352941 collateral, of
+    // which 0.12353 is withheld as the transfer fee.
+    const received = new BN(12_229_411);
+
+    await expectProgramError(
+      fixture,
+      liquidate(new BN(10_000_000), received.addn(1)),
+      "LiquidationSlippageExceeded"
+    );
+
+    await liquidate(new BN(10_000_000), received);
+
+    const liquidatorCollateral = await tokenBalance(
+      fixture.context,
+      getAssociatedTokenAddressSync(
+        collateralMint,
+        liquidator.publicKey,
+        false,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    expect(liquidatorCollateral.toString()).toBe(received.toString());
+  });
+});
@@ -0,0 +1,149 @@
+import { BN, web3 } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { USDC_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants";
+import {
+  LendingFixture,
+  bankAddress,
+  bankConfig,
+  currentTimestamp,
+  depositCollateral,
+  expectProgramError,
+  healthAccounts,
+  initializeBank,
+  initializeUser,
+  oracleConfig,
+  startLendingFixture,
+} from "../bankrun-utils/lendingFixture";
+import { MockPrice, setMockPriceFeed, setMockPriceUpdate } from "../bankrun-utils/mockPriceFeed";
+import { createTestMint, createTokenAccount, mintTokens, tokenBalance } from "../bankrun-utils/tokens";
+
+describe("Oracle fallback and deviation checks", () => {
+  const priceUpdate = web3.Keypair.generate().publicKey;
+  const secondaryPriceFeed = web3.Keypair.generate().publicKey;
+  const maxAge = 60;
+
+  let fixture: LendingFixture;
+  let user: web3.Keypair;
+  let mint: web3.PublicKey;
+  let bank: web3.PublicKey;
+  let userTokenAccount: web3.PublicKey;
+
+  // USD price with eight decimals, published `age` seconds ago.
+  const usdPrice = async (price: number, age: number): Promise<MockPrice> => ({
+    price: new BN(price),
+    conf: new BN(0),
+    exponent: -8,
+    publishTime: (await currentTimestamp(fixture)).subn(age),
+  });
+
+  const setPrices = async (primary: MockPrice, secondary: MockPrice) => {
+    setMockPriceUpdate(fixture.context, priceUpdate, USDC_USD_PRICE_FEED_ID_HEX, primary);
+    setMockPriceFeed(fixture.context, secondaryPriceFeed, fixture.program.programId, secondary);
+  };
+
+  const borrow = (amount: number, secondary: web3.PublicKey | null = secondaryPriceFeed) =>
+    fixture.program.methods
+      .borrow(new BN(amount))
+      .accountsPartial({
+        signer: user.publicKey,
+        market: fixture.market,
+        mint,
+        priceUpdate,
+        secondaryPriceUpdate: secondary,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(healthAccounts(bank, priceUpdate, secondary ?? secondaryPriceFeed))
+      .rpc();
+
+  beforeAll(async () => {
+    fixture = await startLendingFixture();
+    user = fixture.payer;
+
+    mint = await createTestMint(fixture.provider, 6);
+    bank = bankAddress(fixture, mint);
+    await initializeBank(
+      fixture,
+      mint,
+      bankConfig({
+        ...oracleConfig(USDC_USD_PRICE_FEED_ID_HEX),
+        maxAge: new BN(maxAge),
+        secondaryOracle: secondaryPriceFeed,
+        maxDeviationBps: new BN(100),
+      })
+    );
+
+    await initializeUser(fixture, user);
+    userTokenAccount = await createTokenAccount(fixture.provider, mint, user.publicKey);
+    await mintTokens(fixture.provider, mint, userTokenAccount, new BN(1_000_000_000));
+    await depositCollateral(fixture, user, mint, new BN(1_000_000_000));
+  });
+
+  test("Rejects a borrow without the configured secondary price account", async () => {
+    await setPrices(await usdPrice(100_000_000, 0), await usdPrice(100_000_000, 0));
+
+    await expectProgramError(fixture, borrow(1_000_000, null), "InvalidOracleAccount");
+  });
+
+  test("Rejects a secondary price account other than the configured one", async () => {
+    const otherPriceFeed = web3.Keypair.generate().publicKey;
+    await setPrices(await usdPrice(100_000_000, 0), await usdPrice(100_000_000, 0));
+    setMockPriceFeed(
+      fixture.context,
+      otherPriceFeed,
+      fixture.program.programId,
+      await usdPrice(100_000_000, 0)
+    );
+
+    await expectProgramError(fixture, borrow(1_000_001, otherPriceFeed), "InvalidOracleAccount");
+  });
+
+  test("Rejects prices that deviate by more than max_deviation_bps", async () => {
+    await setPrices(await usdPrice(100_000_000, 0), await usdPrice(105_000_000, 0));
+
+    await expectProgramError(fixture, borrow(1_000_002), "PriceDeviationTooHigh");
+  });
+
+  test("Prices from the primary when both sources are fresh and agree", async () => {
+    await setPrices(await usdPrice(100_000_000, 0), await usdPrice(100_500_000, 0));
+
+    const balanceBefore = await tokenBalance(fixture.context, userTokenAccount);
+    await borrow(1_000_003);
+    const balanceAfter = await tokenBalance(fixture.context, userTokenAccount);
+
+    expect(balanceAfter.sub(balanceBefore).toNumber()).toBe(1_000_003);
+  });
+
+  test("Falls back to the secondary price when the primary is stale", async () => {
+    // Both sources fresh at these prices would trip the deviation check, so
+    // the borrow only goes through if the stale primary is ignored.
+    await setPrices(
+      await usdPrice(100_000_000, maxAge + 1),
+      await usdPrice(105_000_000, 0)
+    );
+
+    const balanceBefore = await tokenBalance(fixture.context, userTokenAccount);
+    await borrow(1_000_004);
+    const balanceAfter = await tokenBalance(fixture.context, userTokenAccount);
+
+    expect(balanceAfter.sub(balanceBefore).toNumber()).toBe(1_000_004);
+  });
+
+  test("Refuses to price when both sources are stale", async () => {
+    await setPrices(
+      await usdPrice(100_000_000, maxAge + 1),
+      await usdPrice(100_000_000, maxAge + 1)
+    );
+
+    await expectProgramError(fixture, borrow(1_000_005), "StalePrice");
+  });
+
+  test("Rejects a primary price account not owned by the Pyth receiver", async () => {
+    await setPrices(await usdPrice(100_000_000, 0), await usdPrice(100_000_000, 0));
+    fixture.context.setAccount(priceUpdate, {
+      ...(await fixture.context.banksClient.getAccount(priceUpdate)),
+      owner: fixture.program.programId,
+    });
+
+    await expectProgramError(fixture, borrow(1_000_006), "InvalidOracleAccount");
+  });
+});
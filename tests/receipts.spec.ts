@@ -0,0 +1,116 @@
+import { BN, web3 } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import { USDC_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants";
+import {
+  LendingFixture,
+  bankAddress,
+  bankConfig,
+  bankTokenAccountAddress,
+  deposit,
+  depositCollateral,
+  expectProgramError,
+  fundWallet,
+  initializeBank,
+  initializeUser,
+  oracleConfig,
+  receiptMintAddress,
+  startLendingFixture,
+} from "../bankrun-utils/lendingFixture";
+import {
+  createTestMint,
+  createTokenAccount,
+  mintTokens,
+  tokenBalance,
+  transferTokens,
+} from "../bankrun-utils/tokens";
+
+describe("Receipt tokens", () => {
+  const wad = new BN(10).pow(new BN(18));
+  const recipient = web3.Keypair.generate();
+
+  let fixture: LendingFixture;
+  let holder: web3.Keypair;
+  let mint: web3.PublicKey;
+  let bank: web3.PublicKey;
+  let holderTokenAccount: web3.PublicKey;
+
+  const receiptAccount = (owner: web3.PublicKey) =>
+    getAssociatedTokenAddressSync(receiptMintAddress(fixture, mint), owner);
+
+  const withdraw = (owner: web3.Keypair, amount: BN) =>
+    fixture.program.methods
+      .withdraw(amount)
+      .accountsPartial({
+        signer: owner.publicKey,
+        market: fixture.market,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+
+  beforeAll(async () => {
+    fixture = await startLendingFixture();
+    holder = fixture.payer;
+    fundWallet(fixture, recipient.publicKey);
+
+    mint = await createTestMint(fixture.provider, 6);
+    bank = bankAddress(fixture, mint);
+    await initializeBank(fixture, mint, bankConfig(oracleConfig(USDC_USD_PRICE_FEED_ID_HEX)));
+
+    await initializeUser(fixture, holder);
+    holderTokenAccount = await createTokenAccount(fixture.provider, mint, holder.publicKey);
+    await mintTokens(fixture.provider, mint, holderTokenAccount, new BN(3_000_000_000));
+  });
+
+  test("Redeems transferred receipts for the new holder at the current index", async () => {
+    const receipts = await deposit(fixture, holder, mint, new BN(1_000_000_000));
+
+    // A donation folded in by sync_bank lifts the supply index above one.
+    await transferTokens(
+      fixture.provider,
+      holderTokenAccount,
+      bankTokenAccountAddress(fixture, mint),
+      new BN(100_000_000)
+    );
+    await fixture.program.methods
+      .syncBank()
+      .accountsPartial({ market: fixture.market, mint })
+      .rpc();
+
+    const recipientReceipts = await createTokenAccount(
+      fixture.provider,
+      receiptMintAddress(fixture, mint),
+      recipient.publicKey
+    );
+    await transferTokens(
+      fixture.provider,
+      receiptAccount(holder.publicKey),
+      recipientReceipts,
+      receipts
+    );
+
+    const bankState = await fixture.program.account.bank.fetch(bank);
+    expect(bankState.supplyIndex.gt(wad)).toBe(true);
+    const redeemable = receipts.mul(bankState.supplyIndex).div(wad);
+
+    await expectProgramError(fixture, withdraw(recipient, redeemable.addn(1)), "InsufficientFunds");
+    await withdraw(recipient, redeemable);
+
+    const recipientBalance = await tokenBalance(
+      fixture.context,
+      getAssociatedTokenAddressSync(mint, recipient.publicKey)
+    );
+    expect(recipientBalance.toString()).toBe(redeemable.toString());
+    expect((await tokenBalance(fixture.context, recipientReceipts)).toNumber()).toBe(0);
+  });
+
+  test("Does not redeem receipts locked as collateral", async () => {
+    const locked = await depositCollateral(fixture, holder, mint, new BN(1_000_000_000));
+    expect(locked.gtn(0)).toBe(true);
+    const unlocked = await tokenBalance(fixture.context, receiptAccount(holder.publicKey));
+    expect(unlocked.toNumber()).toBe(0);
+
+    await expectProgramError(fixture, withdraw(holder, new BN(1_000)), "InsufficientFunds");
+  });
+});
@@ -0,0 +1,104 @@
+import { BN, web3 } from "@coral-xyz/anchor";
+import { USDC_USD_PRICE_FEED_ID_HEX } from "../bankrun-utils/constants";
+import {
+  LendingFixture,
+  bankAddress,
+  bankConfig,
+  bankTokenAccountAddress,
+  deposit,
+  fundWallet,
+  initializeBank,
+  initializeUser,
+  oracleConfig,
+  startLendingFixture,
+} from "../bankrun-utils/lendingFixture";
+import { createTestMint, createTokenAccount, mintTokens, transferTokens } from "../bankrun-utils/tokens";
+
+describe("Bank sync", () => {
+  const donation = new BN(1_000_000_000);
+  const wad = new BN(10).pow(new BN(18));
+
+  let fixture: LendingFixture;
+  let attacker: web3.Keypair;
+  let mint: web3.PublicKey;
+  let bank: web3.PublicKey;
+  let attackerTokenAccount: web3.PublicKey;
+
+  // Sends `count` sync_bank instructions in a single transaction.
+  const syncMany = async (count: number) => {
+    const syncIx = await fixture.program.methods
+      .syncBank()
+      .accountsPartial({ market: fixture.market, mint })
+      .instruction();
+    await fixture.provider.sendAndConfirm(
+      new web3.Transaction().add(...Array(count).fill(syncIx))
+    );
+  };
+
+  beforeAll(async () => {
+    fixture = await startLendingFixture();
+    attacker = fixture.payer;
+
+    mint = await createTestMint(fixture.provider, 6);
+    bank = bankAddress(fixture, mint);
+    await initializeBank(fixture, mint, {
+      ...bankConfig(oracleConfig(USDC_USD_PRICE_FEED_ID_HEX)),
+      reserveFactor: new BN(0),
+    });
+
+    await initializeUser(fixture, attacker);
+    attackerTokenAccount = await createTokenAccount(fixture.provider, mint, attacker.publicKey);
+    await mintTokens(fixture.provider, mint, attackerTokenAccount, donation.muln(2));
+  });
+
+  test("Pays a donation out once however often the bank is synced", async () => {
+    await deposit(fixture, attacker, mint, new BN(1));
+    await transferTokens(
+      fixture.provider,
+      attackerTokenAccount,
+      bankTokenAccountAddress(fixture, mint),
+      donation
+    );
+
+    await syncMany(10);
+    const bankAfterFirstSyncs = await fixture.program.account.bank.fetch(bank);
+    await syncMany(9);
+    const bankAfterLaterSyncs = await fixture.program.account.bank.fetch(bank);
+
+    expect(bankAfterLaterSyncs.supplyIndex.toString()).toBe(
+      bankAfterFirstSyncs.supplyIndex.toString()
+    );
+    expect(bankAfterLaterSyncs.virtualReserve.toString()).toBe(
+      bankAfterFirstSyncs.virtualReserve.toString()
+    );
+
+    // The single deposited share only earns its part of the donation, the
+    // rest is booked against the virtual shares.
+    const shares = bankAfterFirstSyncs.totalDepositsShares;
+    const virtualShares = bankAfterFirstSyncs.virtualDepositShares;
+    expect(
+      bankAfterFirstSyncs.totalDeposits.lte(
+        donation.mul(shares).div(shares.add(virtualShares)).addn(1)
+      )
+    ).toBe(true);
+    expect(
+      bankAfterFirstSyncs.totalDeposits.add(bankAfterFirstSyncs.virtualReserve).toString()
+    ).toBe(donation.addn(1).toString());
+  });
+
+  test("Still mints fair receipts for a small deposit after the syncs", async () => {
+    const victim = web3.Keypair.generate();
+    fundWallet(fixture, victim.publicKey);
+    const victimTokenAccount = await createTokenAccount(fixture.provider, mint, victim.publicKey);
+    const amount = new BN(10_000_000);
+    await mintTokens(fixture.provider, mint, victimTokenAccount, amount);
+    await initializeUser(fixture, victim);
+
+    const receipts = await deposit(fixture, victim, mint, amount);
+    const bankState = await fixture.program.account.bank.fetch(bank);
+    const value = receipts.mul(bankState.supplyIndex).div(wad);
+
+    expect(receipts.gtn(0)).toBe(true);
+    expect(value.muln(1_000).gte(amount.muln(998))).toBe(true);
+  });
+});